@@ -27,8 +27,12 @@
 //! `E0282: type annotations needed`, make sure you have a `Metadata` type or you
 //! specify its correct name as an attribute parameter to the macro.
 //!
+//! Unknown or invalid attribute parameters are reported as compile errors pointing at
+//! the offending parameter or value, all at once rather than one per build.
+//!
 //! If parsing does not work for you, make sure to use the attribute parameters to
-//! ensure the actual names used by Porta match with the generated code.
+//! ensure the actual names used by Porta match with the generated code. The derived
+//! keys are exposed as the `SNAKE` and `PLURAL_SNAKE` associated consts of the model.
 //!
 //! ```example
 //! #[derive(Deserialize)]
@@ -48,6 +52,26 @@
 //! Ultimately you can take a look at the generated code via tools like cargo expand
 //! and see debugging info for this crate via the `macro-debug` feature.
 //!
+//! # Features
+//!
+//! - `json`: Generates helpers that use `serde_json` directly, such as parsing bare
+//!   items, newline-delimited items, or collections that Porta returns double-encoded
+//!   as a JSON string. You are required
+//!   to have `serde_json` as a dependency when enabling this feature.
+//! - `proptest`: Allows generating `proptest` strategies for collections of resources
+//!   implementing `proptest::arbitrary::Arbitrary`. You are required to have `proptest`
+//!   as a dependency when enabling this feature.
+//! - `csv`: Allows exporting collections as CSV via the `csv` crate. You are required
+//!   to have `csv` as a dependency when enabling this feature.
+//! - `bincode`: Allows caching collections in a binary format via the `bincode` crate.
+//!   You are required to have `bincode` as a dependency when enabling this feature.
+//! - `tokio`: Generates an async `from_reader` parsing collections out of a
+//!   `tokio::io::AsyncRead`. Implies `json`. You are required to have `tokio` as a
+//!   dependency when enabling this feature.
+//! - `reqwest`: Generates an async `from_response` parsing collections out of a
+//!   `reqwest::Response`, telling HTTP errors apart from parsing errors. Implies `json`.
+//!   You are required to have `reqwest` as a dependency when enabling this feature.
+//!
 
 use proc_macro::TokenStream;
 use quote::quote;
@@ -70,17 +94,66 @@ macro_rules! macro_debug {
 }
 
 mod sj {
-    use proc_macro2::Ident;
+    use proc_macro2::{Ident, Span};
+    use std::collections::HashMap;
+    use syn::{Field, Generics, NestedMeta, Path, Type, Visibility};
 
     #[derive(Debug, Clone)]
     pub struct StraitJacket {
-        name: Ident,
-        name_snake: Ident,
-        name_and_metadata: Ident,
-        name_tag: Ident,
-        plural: Ident,
-        plural_snake: Ident,
-        metadata: Ident,
+        pub(super) name: Ident,
+        pub(super) vis: Visibility,
+        pub(super) generics: Generics,
+        pub(super) name_snake: String,
+        pub(super) name_and_metadata: Ident,
+        pub(super) name_tag: Ident,
+        pub(super) plural: Ident,
+        pub(super) plural_snake: Ident,
+        pub(super) metadata: Type,
+        pub(super) item_field: Ident,
+        pub(super) tag_variant: Ident,
+        pub(super) id_field: Option<Ident>,
+        pub(super) position_field: Option<Ident>,
+        pub(super) pagination: Option<Ident>,
+        pub(super) resource: Option<Ident>,
+        pub(super) metadata_when: Option<Ident>,
+        pub(super) trait_object: Option<Ident>,
+        pub(super) straitjacketed: Option<Ident>,
+        pub(super) derives: Option<Vec<Path>>,
+        pub(super) fields: Vec<(Ident, Type)>,
+        pub(super) moved_fields: Vec<Field>,
+        pub(super) serde_attrs: Vec<NestedMeta>,
+        pub(super) redact: Vec<Ident>,
+        pub(super) filter_fields: Vec<Ident>,
+        pub(super) no_from: Vec<String>,
+        pub(super) spans: HashMap<String, Span>,
+        pub(super) metadata_rename_all: Option<String>,
+        pub(super) metadata_field: Option<String>,
+        pub(super) nested_item: bool,
+        pub(super) enum_item: bool,
+        pub(super) lenient_items: bool,
+        pub(super) proptest: bool,
+        pub(super) serialize_unwrapped: bool,
+        pub(super) csv: bool,
+        pub(super) case_insensitive_key: bool,
+        pub(super) item_default: bool,
+        pub(super) serialize_null_metadata: bool,
+        pub(super) other_variant: bool,
+        pub(super) strict_items: bool,
+        pub(super) bincode: bool,
+        pub(super) sort_keys: bool,
+        pub(super) skip_metadata: bool,
+        pub(super) track_index: bool,
+        pub(super) multi_case: bool,
+        pub(super) forward_serde: bool,
+        pub(super) metadata_required: bool,
+        pub(super) serialize_metadata: bool,
+        pub(super) metadata_catchall: bool,
+        pub(super) no_plural_key: bool,
+        pub(super) paginated: bool,
+        pub(super) deny_unknown: bool,
+        pub(super) no_plural: bool,
+        pub(super) seed: bool,
+        pub(super) wrap_depth: usize,
     }
 
     macro_rules! getter {
@@ -89,35 +162,131 @@ mod sj {
                 &self.$id
             }
         };
+        ( $id:ident? ) => {
+            pub fn $id(&self) -> Option<&Ident> {
+                self.$id.as_ref()
+            }
+        };
+        ( $id:ident: $ty:ty ) => {
+            pub fn $id(&self) -> $ty {
+                self.$id
+            }
+        };
     }
 
     impl StraitJacket {
         getter!(name);
-        getter!(name_snake);
         getter!(name_and_metadata);
         getter!(name_tag);
         getter!(plural);
         getter!(plural_snake);
-        getter!(metadata);
-
-        pub fn new(
-            name: Ident,
-            name_snake: Ident,
-            name_and_metadata: Ident,
-            name_tag: Ident,
-            plural: Ident,
-            plural_snake: Ident,
-            metadata: Ident,
-        ) -> Self {
-            Self {
-                name,
-                name_snake,
-                name_and_metadata,
-                name_tag,
-                plural,
-                plural_snake,
-                metadata,
-            }
+        getter!(item_field);
+        getter!(tag_variant);
+        getter!(id_field?);
+        getter!(position_field?);
+        getter!(pagination?);
+        getter!(resource?);
+        getter!(metadata_when?);
+        getter!(trait_object?);
+        getter!(straitjacketed?);
+        getter!(nested_item: bool);
+        getter!(enum_item: bool);
+        getter!(lenient_items: bool);
+        getter!(proptest: bool);
+        getter!(serialize_unwrapped: bool);
+        getter!(csv: bool);
+        getter!(case_insensitive_key: bool);
+        getter!(item_default: bool);
+        getter!(serialize_null_metadata: bool);
+        getter!(other_variant: bool);
+        getter!(strict_items: bool);
+        getter!(bincode: bool);
+        getter!(sort_keys: bool);
+        getter!(skip_metadata: bool);
+        getter!(track_index: bool);
+        getter!(multi_case: bool);
+        getter!(forward_serde: bool);
+        getter!(metadata_required: bool);
+        getter!(serialize_metadata: bool);
+        getter!(metadata_catchall: bool);
+        getter!(no_plural_key: bool);
+        getter!(paginated: bool);
+        getter!(deny_unknown: bool);
+        getter!(no_plural: bool);
+        getter!(seed: bool);
+        getter!(wrap_depth: usize);
+
+        pub fn vis(&self) -> &Visibility {
+            &self.vis
+        }
+
+        pub fn generics(&self) -> &Generics {
+            &self.generics
+        }
+
+        pub fn moved_fields(&self) -> &[Field] {
+            self.moved_fields.as_slice()
+        }
+
+        pub fn fields(&self) -> &[(Ident, Type)] {
+            self.fields.as_slice()
+        }
+
+        pub fn metadata(&self) -> &Type {
+            &self.metadata
+        }
+
+        pub fn derives(&self) -> Option<&[Path]> {
+            self.derives.as_deref()
+        }
+
+        pub fn serde_attrs(&self) -> &[NestedMeta] {
+            self.serde_attrs.as_slice()
+        }
+
+        pub fn redact(&self) -> &[Ident] {
+            self.redact.as_slice()
+        }
+
+        pub fn filter_fields(&self) -> &[Ident] {
+            self.filter_fields.as_slice()
+        }
+
+        pub fn no_from(&self) -> &[String] {
+            self.no_from.as_slice()
+        }
+
+        /// Whether the `From` implementation known as `key` is generated.
+        pub fn generates_from(&self, key: &str) -> bool {
+            !self.no_from.iter().any(|no_from| no_from == key)
+        }
+
+        /// The remote copy of the item filling in sparse items, see `item_default`.
+        pub fn name_defaults(&self) -> Ident {
+            quote::format_ident!("{}Defaults", self.name)
+        }
+
+        pub fn name_snake(&self) -> &str {
+            &self.name_snake
+        }
+
+        pub fn metadata_rename_all(&self) -> Option<&str> {
+            self.metadata_rename_all.as_deref()
+        }
+
+        pub fn metadata_field(&self) -> Option<&str> {
+            self.metadata_field.as_deref()
+        }
+
+        /// Where the attribute known as `key` was given, to point errors at it.
+        pub fn span(&self, key: &str) -> Span {
+            self.spans.get(key).copied().unwrap_or_else(Span::call_site)
+        }
+
+        pub fn field_type(&self, field: &Ident) -> Option<&Type> {
+            self.fields
+                .iter()
+                .find_map(|(ident, ty)| if ident == field { Some(ty) } else { None })
         }
     }
 
@@ -134,11 +303,239 @@ mod builder {
     use super::StraitJacket;
     use inflector::Inflector;
     use proc_macro2::{Ident, Span};
+    use std::collections::HashMap;
+    use syn::punctuated::Punctuated;
+    use syn::{Field, Generics, NestedMeta, Path, Token, Type, Visibility};
+
+    // Rust keywords that would otherwise produce an invalid field ident
+    const KEYWORDS: &[&str] = &[
+        "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do",
+        "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in",
+        "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+        "return", "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe",
+        "unsized", "use", "virtual", "where", "while", "yield",
+    ];
+
+    // path keywords that can't be used as raw identifiers either
+    const RESERVED: &[&str] = &["crate", "self", "Self", "super"];
+
+    /// Builds an ident, falling back to a raw identifier (`r#type`) for keywords.
+    fn ident(value: &str) -> Ident {
+        if KEYWORDS.contains(&value) {
+            Ident::new_raw(value, Span::call_site())
+        } else {
+            Ident::new(value, Span::call_site())
+        }
+    }
+
+    // trailing words that `to_plural` gets wrong for Porta resources, ie. `Data` -> `Datas`
+    const UNCOUNTABLE: &[&str] = &[
+        "analytics",
+        "data",
+        "information",
+        "metadata",
+        "series",
+        "settings",
+    ];
+    const IRREGULAR: &[(&str, &str)] = &[("proxy", "proxies"), ("status", "statuses")];
+
+    /// Pluralizes a type name by its trailing word, ie. `BackendUsage` looks at `Usage`,
+    /// before falling back to `to_plural`.
+    fn pluralize(name: &str) -> String {
+        let split = name
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_uppercase())
+            .map_or(0, |(idx, _)| idx);
+        let (head, tail) = name.split_at(split);
+        let lower = tail.to_lowercase();
+
+        if UNCOUNTABLE.contains(&lower.as_str()) {
+            return name.to_string();
+        }
+        match IRREGULAR.iter().find(|(singular, _)| *singular == lower) {
+            Some((_, plural)) if tail.starts_with(char::is_uppercase) => {
+                format!("{}{}", head, plural.to_pascal_case())
+            }
+            Some((_, plural)) => format!("{}{}", head, plural),
+            None => name.to_plural(),
+        }
+    }
+
+    // acronyms `to_snake_case` would split, ie. `OAuth` -> `o_auth`
+    const ACRONYMS: &[&str] = &["API", "ID", "URL", "OAuth"];
+
+    /// Converts a type name to snake case keeping acronyms as single words, ie. `OAuthConfig`
+    /// becomes `oauth_config` rather than `o_auth_config`.
+    fn snake_case(name: &str, acronyms: &[String]) -> String {
+        let mut acronyms = ACRONYMS
+            .iter()
+            .copied()
+            .chain(acronyms.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+        // longest first so that ie. `OpenID` wins over `ID`
+        acronyms.sort_by_key(|acronym| std::cmp::Reverse(acronym.len()));
+
+        // acronyms only match whole words, starting either the name or at a transition
+        // to uppercase, so that `ID` is left alone within `OIDC` and `sso` within `Lasso`
+        let mut word = String::with_capacity(name.len());
+        let mut rest = name;
+        while let Some(c) = rest.chars().next() {
+            let starts_word = |acronym: &str| {
+                word.is_empty()
+                    || (acronym.starts_with(char::is_uppercase)
+                        && !word.ends_with(char::is_uppercase))
+            };
+            let acronym = acronyms.iter().find(|acronym| {
+                starts_word(acronym)
+                    && rest.starts_with(*acronym)
+                    && !rest[acronym.len()..]
+                        .trim_start_matches('s')
+                        .starts_with(char::is_lowercase)
+            });
+            match acronym {
+                Some(acronym) => {
+                    word.push_str(acronym.to_lowercase().to_pascal_case().as_str());
+                    rest = &rest[acronym.len()..];
+                }
+                None => {
+                    word.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+        word.to_snake_case()
+    }
+
+    /// The attributes accepted by `set`, for diagnostics.
+    pub const ATTRIBUTES: &[&str] = &[
+        "name_snake",
+        "name_and_metadata",
+        "name_tag",
+        "plural",
+        "plural_snake",
+        "metadata",
+        "item_field",
+        "tag_variant",
+        "id_field",
+        "position_field",
+        "pagination",
+        "resource",
+        "metadata_when",
+        "wrap_depth",
+        "redact",
+        "filter_fields",
+        "metadata_rename_all",
+        "trait_object",
+        "derives",
+        "metadata_field",
+        "acronyms",
+        "no_from",
+        "straitjacketed",
+    ];
+
+    /// The `From` implementations that `no_from` can suppress.
+    pub const FROM_IMPLS: &[&str] = &[
+        "vec_to_plural",
+        "plural_to_vec",
+        "plural_to_vec_and_metadata",
+    ];
+
+    /// Checks that an attribute's value can be parsed, so that invalid values are reported
+    /// at their span rather than panicking or being ignored.
+    pub fn validate(field: &str, value: &str) -> Result<(), String> {
+        let is_ident = |value: &str| {
+            let mut chars = value.chars();
+            chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+                && chars.all(|c| c.is_alphanumeric() || c == '_')
+                && !RESERVED.contains(&value)
+        };
+
+        match field {
+            "metadata" => syn::parse_str::<Type>(value)
+                .map(|_| ())
+                .map_err(|_| format!("`{}` is not a valid type", value)),
+            "derives" => {
+                let parser = Punctuated::<Path, Token![,]>::parse_terminated;
+                syn::parse::Parser::parse_str(parser, value)
+                    .map(|_| ())
+                    .map_err(|_| format!("`{}` is not a comma separated list of paths", value))
+            }
+            "wrap_depth" => match value.parse::<usize>() {
+                Ok(depth) if depth > 0 => Ok(()),
+                _ => Err(format!("`{}` is not a positive integer", value)),
+            },
+            "redact" | "filter_fields" => value
+                .split(',')
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .find(|field| !is_ident(field))
+                .map_or(Ok(()), |field| {
+                    Err(format!("`{}` is not a valid identifier", field))
+                }),
+            "no_from" => value
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .find(|key| !FROM_IMPLS.contains(key))
+                .map_or(Ok(()), |key| {
+                    Err(format!(
+                        "`{}` is not one of: {}",
+                        key,
+                        FROM_IMPLS.join(", ")
+                    ))
+                }),
+            "metadata_rename_all" | "metadata_field" | "acronyms" => Ok(()),
+            _ if is_ident(value) => Ok(()),
+            _ if RESERVED.contains(&value) => Err(format!(
+                "`{}` is a reserved keyword and can't be used as an identifier",
+                value
+            )),
+            _ => Err(format!("`{}` is not a valid identifier", value)),
+        }
+    }
+
+    /// The flags accepted by `set_flag`, for diagnostics.
+    pub const FLAGS: &[&str] = &[
+        "lenient_items",
+        "proptest",
+        "serialize_unwrapped",
+        "csv",
+        "case_insensitive_key",
+        "item_default",
+        "serialize_null_metadata",
+        "other_variant",
+        "strict_items",
+        "bincode",
+        "sort_keys",
+        "skip_metadata",
+        "track_index",
+        "multi_case",
+        "forward_serde",
+        "metadata_required",
+        "serialize_metadata",
+        "metadata_catchall",
+        "no_plural_key",
+        "paginated",
+        "deny_unknown",
+        "no_plural",
+        "seed",
+    ];
+
+    /// Builds idents out of a comma separated list, ie. `"token, secret"`.
+    fn idents(value: &str) -> Vec<Ident> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(ident)
+            .collect()
+    }
 
     macro_rules! attribute {
         ( $id:ident ) => {
             pub fn $id(mut self, value: &str) -> Self {
-                let _ = self.$id.replace(Ident::new(value, Span::call_site()));
+                let _ = self.$id.replace(ident(value));
                 self
             }
         };
@@ -148,7 +545,16 @@ mod builder {
             }
 
             pub fn $id(mut self, value: &str) -> Self {
-                let _ = self.$id.replace(Ident::new(value, Span::call_site()));
+                let _ = self.$id.replace(ident(value));
+                self
+            }
+        };
+    }
+
+    macro_rules! flag {
+        ( $id:ident ) => {
+            pub fn $id(mut self, value: bool) -> Self {
+                self.$id = value;
                 self
             }
         };
@@ -157,108 +563,509 @@ mod builder {
     #[derive(Debug, Clone)]
     pub struct StraitJacketBuilder {
         name: Ident,
-        name_snake: Option<Ident>,
+        vis: Option<Visibility>,
+        generics: Generics,
+        name_snake: Option<String>,
         name_and_metadata: Option<Ident>,
         name_tag: Option<Ident>,
         plural: Option<Ident>,
         plural_snake: Option<Ident>,
-        metadata: Option<Ident>,
+        metadata: Option<Type>,
+        item_field: Option<Ident>,
+        tag_variant: Option<Ident>,
+        id_field: Option<Ident>,
+        position_field: Option<Ident>,
+        pagination: Option<Ident>,
+        resource: Option<Ident>,
+        metadata_when: Option<Ident>,
+        trait_object: Option<Ident>,
+        straitjacketed: Option<Ident>,
+        derives: Option<Vec<Path>>,
+        fields: Vec<(Ident, Type)>,
+        moved_fields: Vec<Field>,
+        serde_attrs: Vec<NestedMeta>,
+        redact: Vec<Ident>,
+        filter_fields: Vec<Ident>,
+        no_from: Vec<String>,
+        spans: HashMap<String, Span>,
+        metadata_rename_all: Option<String>,
+        metadata_field: Option<String>,
+        acronyms: Vec<String>,
+        nested_item: bool,
+        enum_item: bool,
+        lenient_items: bool,
+        proptest: bool,
+        serialize_unwrapped: bool,
+        csv: bool,
+        case_insensitive_key: bool,
+        item_default: bool,
+        serialize_null_metadata: bool,
+        other_variant: bool,
+        strict_items: bool,
+        bincode: bool,
+        sort_keys: bool,
+        skip_metadata: bool,
+        track_index: bool,
+        multi_case: bool,
+        forward_serde: bool,
+        metadata_required: bool,
+        serialize_metadata: bool,
+        metadata_catchall: bool,
+        no_plural_key: bool,
+        paginated: bool,
+        deny_unknown: bool,
+        no_plural: bool,
+        seed: bool,
+        wrap_depth: Option<usize>,
     }
 
     impl StraitJacketBuilder {
         pub fn new(name: Ident) -> Self {
             Self {
                 name,
+                vis: None,
+                generics: Generics::default(),
                 name_snake: None,
                 name_and_metadata: None,
                 name_tag: None,
                 plural: None,
                 plural_snake: None,
                 metadata: None,
+                item_field: None,
+                tag_variant: None,
+                id_field: None,
+                position_field: None,
+                pagination: None,
+                resource: None,
+                metadata_when: None,
+                trait_object: None,
+                straitjacketed: None,
+                derives: None,
+                fields: Vec::new(),
+                moved_fields: Vec::new(),
+                serde_attrs: Vec::new(),
+                redact: Vec::new(),
+                filter_fields: Vec::new(),
+                no_from: Vec::new(),
+                spans: HashMap::new(),
+                metadata_rename_all: None,
+                metadata_field: None,
+                acronyms: Vec::new(),
+                nested_item: false,
+                enum_item: false,
+                lenient_items: false,
+                proptest: false,
+                serialize_unwrapped: false,
+                csv: false,
+                case_insensitive_key: false,
+                item_default: false,
+                serialize_null_metadata: false,
+                other_variant: false,
+                strict_items: false,
+                bincode: false,
+                sort_keys: false,
+                skip_metadata: false,
+                track_index: false,
+                multi_case: false,
+                forward_serde: false,
+                metadata_required: false,
+                serialize_metadata: false,
+                metadata_catchall: false,
+                no_plural_key: false,
+                paginated: false,
+                deny_unknown: false,
+                no_plural: false,
+                seed: false,
+                wrap_depth: None,
             }
         }
 
-        attribute!(name_snake);
         attribute!(name_and_metadata);
         attribute!(name_tag);
         attribute!(plural);
         attribute!(plural_snake);
-        attribute!(metadata);
+        attribute!(item_field);
+        attribute!(tag_variant);
+        attribute!(id_field);
+        attribute!(position_field);
+        attribute!(pagination);
+        attribute!(resource);
+        attribute!(metadata_when);
+        attribute!(trait_object);
+        attribute!(straitjacketed);
+
+        pub fn fields(mut self, fields: Vec<(Ident, Type)>) -> Self {
+            self.fields = fields;
+            self
+        }
+
+        pub fn vis(mut self, vis: Visibility) -> Self {
+            self.vis = Some(vis);
+            self
+        }
+
+        pub fn generics(mut self, generics: Generics) -> Self {
+            self.generics = generics;
+            self
+        }
+
+        /// Sets `name_snake` unless it was explicitly specified.
+        pub fn default_name_snake(mut self, value: Option<String>) -> Self {
+            if self.name_snake.is_none() {
+                self.name_snake = value;
+            }
+            self
+        }
 
-        pub fn set(self, field: &str, value: &str) -> Self {
-            match field {
+        pub fn moved_fields(mut self, moved_fields: Vec<Field>) -> Self {
+            self.moved_fields = moved_fields;
+            self
+        }
+
+        pub fn serde_attrs(mut self, serde_attrs: Vec<NestedMeta>) -> Self {
+            self.serde_attrs = serde_attrs;
+            self
+        }
+
+        /// Records where an attribute was given, to point errors at it.
+        pub fn span(mut self, key: &str, span: Span) -> Self {
+            self.spans.insert(key.to_string(), span);
+            self
+        }
+
+        flag!(nested_item);
+        flag!(enum_item);
+        flag!(lenient_items);
+        flag!(proptest);
+        flag!(serialize_unwrapped);
+        flag!(csv);
+        flag!(case_insensitive_key);
+        flag!(item_default);
+        flag!(serialize_null_metadata);
+        flag!(other_variant);
+        flag!(strict_items);
+        flag!(bincode);
+        flag!(sort_keys);
+        flag!(skip_metadata);
+        flag!(track_index);
+        flag!(multi_case);
+        flag!(forward_serde);
+        flag!(metadata_required);
+        flag!(serialize_metadata);
+        flag!(metadata_catchall);
+        flag!(no_plural_key);
+        flag!(paginated);
+        flag!(deny_unknown);
+        flag!(no_plural);
+        flag!(seed);
+
+        pub fn wrap_depth(mut self, value: &str) -> Self {
+            match value.parse::<usize>() {
+                Ok(depth) if depth > 0 => {
+                    let _ = self.wrap_depth.replace(depth);
+                }
+                _ => macro_debug!("invalid wrap_depth {:#?}", value),
+            }
+            self
+        }
+
+        pub fn metadata(mut self, value: &str) -> Self {
+            if let Ok(ty) = syn::parse_str::<Type>(value) {
+                let _ = self.metadata.replace(ty);
+            } else {
+                macro_debug!("invalid metadata {:#?}", value);
+            }
+            self
+        }
+
+        pub fn derives(mut self, value: &str) -> Self {
+            let parser = Punctuated::<Path, Token![,]>::parse_terminated;
+            if let Ok(paths) = syn::parse::Parser::parse_str(parser, value) {
+                let _ = self.derives.replace(paths.into_iter().collect());
+            } else {
+                macro_debug!("invalid derives {:#?}", value);
+            }
+            self
+        }
+
+        pub fn redact(mut self, value: &str) -> Self {
+            self.redact = idents(value);
+            self
+        }
+
+        pub fn filter_fields(mut self, value: &str) -> Self {
+            self.filter_fields = idents(value);
+            self
+        }
+
+        pub fn no_from(mut self, value: &str) -> Self {
+            self.no_from = value
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string)
+                .collect();
+            self
+        }
+
+        pub fn name_snake(mut self, value: &str) -> Self {
+            let _ = self.name_snake.replace(value.to_string());
+            self
+        }
+
+        pub fn metadata_rename_all(mut self, value: &str) -> Self {
+            let _ = self.metadata_rename_all.replace(value.to_string());
+            self
+        }
+
+        pub fn metadata_field(mut self, value: &str) -> Self {
+            let _ = self.metadata_field.replace(value.to_string());
+            self
+        }
+
+        pub fn acronyms(mut self, value: &str) -> Self {
+            self.acronyms = value
+                .split(',')
+                .map(str::trim)
+                .filter(|acronym| !acronym.is_empty())
+                .map(str::to_string)
+                .collect();
+            self
+        }
+
+        /// Sets an attribute, or gives the builder back unchanged if the key is unknown.
+        // the builder only lives during expansion, so its size is of no concern
+        #[allow(clippy::result_large_err)]
+        pub fn set(self, field: &str, value: &str) -> Result<Self, Self> {
+            Ok(match field {
                 "name_snake" => self.name_snake(value),
                 "name_and_metadata" => self.name_and_metadata(value),
                 "name_tag" => self.name_tag(value),
                 "plural" => self.plural(value),
                 "plural_snake" => self.plural_snake(value),
                 "metadata" => self.metadata(value),
-                _ => {
-                    macro_debug!("unknown attribute {:#?}", field);
-                    self
-                }
-            }
+                "item_field" => self.item_field(value),
+                "tag_variant" => self.tag_variant(value),
+                "id_field" => self.id_field(value),
+                "position_field" => self.position_field(value),
+                "pagination" => self.pagination(value),
+                "resource" => self.resource(value),
+                "metadata_when" => self.metadata_when(value),
+                "wrap_depth" => self.wrap_depth(value),
+                "redact" => self.redact(value),
+                "filter_fields" => self.filter_fields(value),
+                "no_from" => self.no_from(value),
+                "metadata_rename_all" => self.metadata_rename_all(value),
+                "trait_object" => self.trait_object(value),
+                "derives" => self.derives(value),
+                "metadata_field" => self.metadata_field(value),
+                "acronyms" => self.acronyms(value),
+                "straitjacketed" => self.straitjacketed(value),
+                _ => return Err(self),
+            })
+        }
+
+        /// Sets a flag, or gives the builder back unchanged if the flag is unknown.
+        #[allow(clippy::result_large_err)]
+        pub fn set_flag(self, field: &str) -> Result<Self, Self> {
+            self.set_bool(field, true)
+        }
+
+        /// Sets a flag to a boolean value, ie. for `flag = false`, or gives the builder
+        /// back unchanged if the flag is unknown.
+        #[allow(clippy::result_large_err)]
+        pub fn set_bool(self, field: &str, value: bool) -> Result<Self, Self> {
+            Ok(match field {
+                "lenient_items" => self.lenient_items(value),
+                "proptest" => self.proptest(value),
+                "serialize_unwrapped" => self.serialize_unwrapped(value),
+                "csv" => self.csv(value),
+                "case_insensitive_key" => self.case_insensitive_key(value),
+                "item_default" => self.item_default(value),
+                "serialize_null_metadata" => self.serialize_null_metadata(value),
+                "other_variant" => self.other_variant(value),
+                "strict_items" => self.strict_items(value),
+                "bincode" => self.bincode(value),
+                "sort_keys" => self.sort_keys(value),
+                "skip_metadata" => self.skip_metadata(value),
+                "track_index" => self.track_index(value),
+                "multi_case" => self.multi_case(value),
+                "forward_serde" => self.forward_serde(value),
+                "metadata_required" => self.metadata_required(value),
+                "serialize_metadata" => self.serialize_metadata(value),
+                "metadata_catchall" => self.metadata_catchall(value),
+                "no_plural_key" => self.no_plural_key(value),
+                "paginated" => self.paginated(value),
+                "deny_unknown" => self.deny_unknown(value),
+                "no_plural" => self.no_plural(value),
+                "seed" => self.seed(value),
+                _ => return Err(self),
+            })
         }
 
         pub fn build(self) -> StraitJacket {
             use quote::format_ident;
 
             let name_s = self.name.to_string();
-            let plural = name_s.to_plural();
+            let acronyms = self.acronyms;
+            // a user provided plural drives the default snake case plural as well
+            let plural = self
+                .plural
+                .as_ref()
+                .map(|plural| plural.to_string())
+                .unwrap_or_else(|| pluralize(name_s.as_str()));
 
-            StraitJacket::new(
-                self.name,
-                self.name_snake.unwrap_or_else(|| {
-                    Ident::new(name_s.to_snake_case().as_str(), Span::call_site())
-                }),
-                self.name_and_metadata
+            StraitJacket {
+                name: self.name,
+                vis: self.vis.unwrap_or(Visibility::Inherited),
+                generics: self.generics,
+                name_snake: self
+                    .name_snake
+                    .unwrap_or_else(|| snake_case(name_s.as_str(), &acronyms)),
+                name_and_metadata: self
+                    .name_and_metadata
                     .unwrap_or_else(|| format_ident!("{}AndMetadata", name_s)),
-                self.name_tag
+                name_tag: self
+                    .name_tag
                     .unwrap_or_else(|| format_ident!("{}Tag", name_s)),
-                self.plural
-                    .unwrap_or_else(|| Ident::new(plural.as_str(), Span::call_site())),
-                self.plural_snake.unwrap_or_else(|| {
-                    Ident::new(plural.to_snake_case().as_str(), Span::call_site())
+                // uncountable names would clash with the item's own type
+                plural: self.plural.unwrap_or_else(|| {
+                    if plural == name_s {
+                        format_ident!("{}List", name_s)
+                    } else {
+                        Ident::new(plural.as_str(), Span::call_site())
+                    }
                 }),
-                self.metadata
-                    .unwrap_or_else(|| Ident::new("Metadata", Span::call_site())),
-            )
+                plural_snake: self
+                    .plural_snake
+                    .unwrap_or_else(|| ident(snake_case(plural.as_str(), &acronyms).as_str())),
+                metadata: self
+                    .metadata
+                    .unwrap_or_else(|| syn::parse_quote! { Metadata }),
+                item_field: self
+                    .item_field
+                    .unwrap_or_else(|| Ident::new("item", Span::call_site())),
+                tag_variant: self
+                    .tag_variant
+                    .unwrap_or_else(|| Ident::new("Tag", Span::call_site())),
+                id_field: self.id_field,
+                position_field: self.position_field,
+                pagination: self.pagination,
+                resource: self.resource,
+                metadata_when: self.metadata_when,
+                trait_object: self.trait_object,
+                straitjacketed: self.straitjacketed,
+                derives: self.derives,
+                fields: self.fields,
+                moved_fields: self.moved_fields,
+                serde_attrs: self.serde_attrs,
+                redact: self.redact,
+                filter_fields: self.filter_fields,
+                no_from: self.no_from,
+                spans: self.spans,
+                metadata_rename_all: self.metadata_rename_all,
+                metadata_field: self.metadata_field,
+                nested_item: self.nested_item,
+                enum_item: self.enum_item,
+                lenient_items: self.lenient_items,
+                proptest: self.proptest,
+                serialize_unwrapped: self.serialize_unwrapped,
+                csv: self.csv,
+                case_insensitive_key: self.case_insensitive_key,
+                item_default: self.item_default,
+                serialize_null_metadata: self.serialize_null_metadata,
+                other_variant: self.other_variant,
+                strict_items: self.strict_items,
+                bincode: self.bincode,
+                sort_keys: self.sort_keys,
+                skip_metadata: self.skip_metadata,
+                track_index: self.track_index,
+                multi_case: self.multi_case,
+                forward_serde: self.forward_serde,
+                metadata_required: self.metadata_required,
+                serialize_metadata: self.serialize_metadata,
+                metadata_catchall: self.metadata_catchall,
+                no_plural_key: self.no_plural_key,
+                paginated: self.paginated,
+                deny_unknown: self.deny_unknown,
+                no_plural: self.no_plural,
+                seed: self.seed,
+                wrap_depth: self.wrap_depth.unwrap_or(1),
+            }
         }
     }
 }
 
 mod parser {
-    use syn::{Ident, Lit, MetaNameValue, NestedMeta};
+    use syn::parse::{Parse, ParseStream};
+    use syn::punctuated::Punctuated;
+    use syn::{
+        parenthesized, Attribute, Data, DataStruct, DeriveInput, Field, Fields, Ident, Lit, Meta,
+        MetaNameValue, NestedMeta, Token, Type, Visibility,
+    };
+
+    /// A list of resources along their visibility and attributes, ie.
+    /// `pub A(plural = "As"), B`.
+    pub struct Resources(Vec<(Visibility, Ident, Vec<NestedMeta>)>);
+
+    impl Resources {
+        pub fn iter(&self) -> impl Iterator<Item = &(Visibility, Ident, Vec<NestedMeta>)> {
+            self.0.iter()
+        }
+    }
+
+    impl Parse for Resources {
+        fn parse(input: ParseStream) -> syn::Result<Self> {
+            let resources = Punctuated::<Resource, Token![,]>::parse_terminated(input)?;
+            Ok(Resources(
+                resources
+                    .into_iter()
+                    .map(|resource| (resource.vis, resource.name, resource.attributes))
+                    .collect(),
+            ))
+        }
+    }
+
+    struct Resource {
+        vis: Visibility,
+        name: Ident,
+        attributes: Vec<NestedMeta>,
+    }
+
+    impl Parse for Resource {
+        fn parse(input: ParseStream) -> syn::Result<Self> {
+            let vis = input.parse()?;
+            let name = input.parse()?;
+            let attributes = if input.peek(syn::token::Paren) {
+                let content;
+                parenthesized!(content in input);
+                Punctuated::<NestedMeta, Token![,]>::parse_terminated(&content)?
+                    .into_iter()
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            Ok(Resource {
+                vis,
+                name,
+                attributes,
+            })
+        }
+    }
 
     fn get_key_value(mnv: &MetaNameValue) -> Option<(&Ident, &Lit)> {
         macro_debug!("Meta(NameValue(mnv)): {:#?}", mnv);
-        match mnv {
-            syn::MetaNameValue {
-                lit: Lit::Str(_lit_str),
-                ..
-            } => match mnv.path.get_ident() {
-                Some(ident) => {
-                    macro_debug!(
-                        "Found attribute {} = {}",
-                        ident.to_string(),
-                        _lit_str.value()
-                    );
-                    Some((ident, &mnv.lit))
-                }
-                None => {
-                    macro_debug!("Found string literal value {} but no suitable attribute name for path {:#?}", _lit_str.value(), mnv.path);
-                    None
-                }
-            },
-            syn::MetaNameValue {
-                lit: _lit,
-                path: _path,
-                ..
-            } => {
+        match mnv.path.get_ident() {
+            Some(ident) => {
+                macro_debug!("Found attribute {} = {:#?}", ident.to_string(), mnv.lit);
+                Some((ident, &mnv.lit))
+            }
+            None => {
                 macro_debug!(
-                    "Found non string literal value {:#?} for path {:#?}",
-                    _lit,
-                    _path
+                    "Found literal value {:#?} but no suitable attribute name for path {:#?}",
+                    mnv.lit,
+                    mnv.path
                 );
                 None
             }
@@ -280,6 +1087,168 @@ mod parser {
             }
         })
     }
+
+    pub fn get_flags(nestedmetas: &[NestedMeta]) -> impl Iterator<Item = &Ident> {
+        nestedmetas
+            .iter()
+            .filter_map(|nestedmeta| match nestedmeta {
+                NestedMeta::Meta(Meta::Path(path)) => {
+                    macro_debug!("Found flag {:#?}", path);
+                    path.get_ident()
+                }
+                _ => None,
+            })
+    }
+
+    pub fn get_fields(data: &Data) -> Vec<(Ident, Type)> {
+        match data {
+            Data::Struct(data) => data
+                .fields
+                .iter()
+                .filter_map(|field| {
+                    field
+                        .ident
+                        .as_ref()
+                        .map(|ident| (ident.clone(), field.ty.clone()))
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn is_moved_into_metadata(attr: &Attribute) -> bool {
+        match attr.parse_meta() {
+            Ok(Meta::List(list)) => list.nested.iter().any(|nestedmeta| match nestedmeta {
+                NestedMeta::Meta(Meta::Path(path)) => path.is_ident("flatten_into_metadata"),
+                _ => false,
+            }),
+            _other => {
+                macro_debug!("Unhandled straitjacket_field attribute: {:#?}", _other);
+                false
+            }
+        }
+    }
+
+    /// Removes fields marked with `#[straitjacket_field(flatten_into_metadata)]` and
+    /// returns them, stripping the marker from every field since it is not a real
+    /// attribute.
+    pub fn take_moved_fields(data: &mut Data) -> Vec<Field> {
+        let fields = match data {
+            Data::Struct(DataStruct {
+                fields: Fields::Named(fields),
+                ..
+            }) => fields,
+            _ => return Vec::new(),
+        };
+
+        let mut moved = Vec::new();
+        let mut kept = Punctuated::new();
+        for mut field in std::mem::take(&mut fields.named).into_iter() {
+            let (markers, attrs) = field
+                .attrs
+                .into_iter()
+                .partition::<Vec<_>, _>(|attr| attr.path.is_ident("straitjacket_field"));
+            field.attrs = attrs;
+
+            if markers.iter().any(is_moved_into_metadata) {
+                moved.push(field);
+            } else {
+                kept.push(field);
+            }
+        }
+        fields.named = kept;
+
+        moved
+    }
+
+    fn is_serde_flatten(attr: &Attribute) -> bool {
+        if !attr.path.is_ident("serde") {
+            return false;
+        }
+
+        match attr.parse_meta() {
+            Ok(Meta::List(list)) => list.nested.iter().any(|nestedmeta| match nestedmeta {
+                NestedMeta::Meta(Meta::Path(path)) => path.is_ident("flatten"),
+                _ => false,
+            }),
+            _other => {
+                macro_debug!("Unhandled serde attribute: {:#?}", _other);
+                false
+            }
+        }
+    }
+
+    /// Returns the container level `#[serde(rename = "...")]` of an item, if any.
+    pub fn get_serde_rename(attrs: &[Attribute]) -> Option<String> {
+        attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("serde"))
+            .filter_map(|attr| match attr.parse_meta() {
+                Ok(Meta::List(list)) => Some(list.nested),
+                _ => None,
+            })
+            .flatten()
+            .find_map(|nestedmeta| match nestedmeta {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => {
+                    match nv.lit {
+                        syn::Lit::Str(lit_str) => Some(lit_str.value()),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+    }
+
+    /// Returns the container level serde attributes of an item which also make sense
+    /// on a wrapper flattening it, ie. `rename_all`.
+    pub fn get_serde_container_attrs(attrs: &[Attribute]) -> Vec<NestedMeta> {
+        const FORWARDED: &[&str] = &["rename_all", "bound", "expecting"];
+
+        attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("serde"))
+            .filter_map(|attr| match attr.parse_meta() {
+                Ok(Meta::List(list)) => Some(list.nested),
+                _ => None,
+            })
+            .flatten()
+            .filter(|nestedmeta| match nestedmeta {
+                NestedMeta::Meta(meta) => FORWARDED.iter().any(|key| meta.path().is_ident(key)),
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Returns a copy of a struct item keeping only its serde attributes, ie. to derive
+    /// a remote deserializer for it with different container level settings.
+    pub fn get_serde_shadow(item: &DeriveInput, ident: Ident) -> DeriveInput {
+        let mut shadow = item.clone();
+        shadow.ident = ident;
+        shadow.vis = Visibility::Inherited;
+        shadow.attrs = Vec::new();
+        if let Data::Struct(data) = &mut shadow.data {
+            for field in data.fields.iter_mut() {
+                field.attrs.retain(|attr| attr.path.is_ident("serde"));
+            }
+        }
+
+        shadow
+    }
+
+    pub fn has_flattened_fields(data: &Data) -> bool {
+        match data {
+            Data::Struct(data) => data
+                .fields
+                .iter()
+                .any(|field| field.attrs.iter().any(is_serde_flatten)),
+            Data::Enum(data) => data
+                .variants
+                .iter()
+                .flat_map(|variant| variant.fields.iter())
+                .any(|field| field.attrs.iter().any(is_serde_flatten)),
+            _ => false,
+        }
+    }
 }
 
 /// The `straitjacket` macro.
@@ -292,150 +1261,2928 @@ mod parser {
 ///
 /// The following set of attributes are accepted to customize the output:
 ///
-/// - `name_snake`: How the model's snake case is represented by Porta.
-/// - `plural`: The plural form of the model. If unspecified a best effort will be used.
-/// - `plural_snake`: The snake case form of the plural used in Porta responses.
-/// - `metadata`: The name of the type to add as metadata for this resource. Note that
-///             this type must be provided by the user, since it depends on the resource.
+/// - `name_snake`: How the model's snake case is represented by Porta. Defaults to the
+///   item's container level `#[serde(rename = "...")]`, if present.
+/// - `plural`: The plural form of the model. If unspecified a best effort will be used,
+///   keeping uncountable trailing words such as `Data` or `Metadata` as they are. The
+///   collection type of an uncountable model is suffixed with `List`, ie. `UsageDataList`.
+/// - `acronyms`: A comma separated list of acronyms, ie. `"SSO, OpenID"`, kept as single
+///   words when deriving the default snake case forms on top of `API`, `ID`, `URL` and
+///   `OAuth`, so that `OAuthConfig` becomes `oauth_config`.
+/// - `plural_snake`: The snake case form of the plural used in Porta responses. Keywords
+///   such as `"match"` are emitted as raw identifiers, so the field becomes `r#match`.
+/// - `metadata`: The type to add as metadata for this resource, optionally as a path or
+///   with generic arguments, ie. `"crate::meta::PlanMetadata"` or `"LinkMeta<u64>"`.
+///   Note that this type must be provided by the user, since it depends on the resource.
+/// - `item_field`: The name of `name_and_metadata`'s field holding the item. Defaults to
+///   `"item"`.
+/// - `tag_variant`: The name of `name_tag`'s variant wrapping each item. Defaults to
+///   `"Tag"`.
+/// - `id_field`: The field identifying each item, ie. `"id"`. Enables helpers such as
+///   `metadata_by_id` or `binary_search_by_id` (for collections sorted by id), and
+///   requires the resource's definition to contain such a field.
+/// - `position_field`: The field ordering items, ie. `"position"`. Enables helpers such
+///   as `take_until_position`, and requires the resource's definition to contain it.
+/// - `pagination`: The name of a type holding pagination fields which Porta returns
+///   alongside the collection. It is flattened into the collection and available via
+///   its `page()` accessor.
+/// - `resource`: The name of a trait, provided by the user, to implement for a generated
+///   zero-sized `Resource` marker type (ie. `MappingRuleResource`) usable for type-safe
+///   routing. The trait must declare the `Item` and `Collection` associated types and
+///   the `SINGULAR_KEY` and `PLURAL_KEY` associated constants.
+/// - `straitjacketed`: The name of a trait, provided by the user, to implement for the
+///   item so that code can be generic over any resource. The trait must declare the
+///   `Plural` and `Metadata` associated types and the `SNAKE` associated constant.
+/// - `metadata_when`: The name of a discriminator field returned alongside the item,
+///   ie. `"detailed"`, so that metadata is only parsed when it is present and not
+///   `false` or `null`. Requires the `json` feature.
+/// - `no_from`: A comma separated list of the generated conversions to leave out, ie.
+///   when they clash with your own: `vec_to_plural` for `From<Vec<name>> for plural`,
+///   `plural_to_vec` for `From<plural> for Vec<name>` and `plural_to_vec_and_metadata`
+///   for `From<plural> for Vec<name_and_metadata>`.
+/// - `filter_fields`: A comma separated list of the item's string fields, ie.
+///   `"http_method"`, for each of which a `with_<field>` method is generated returning
+///   the items whose field equals a given value.
+/// - `metadata_rename_all`: The case Porta uses for the metadata's keys when it differs
+///   from the metadata type's fields, ie. `"camelCase"`, accepting the same rules as
+///   serde's `rename_all`. Requires the `json` feature and cannot be combined with
+///   `metadata_when`.
+/// - `wrap_depth`: How many times each item is wrapped under its snake case name, for
+///   responses wrapping items more than once (ie. due to proxying). Defaults to `"1"`.
+/// - `trait_object`: The name of a trait, provided by the user and implemented by the
+///   item, to generate an `into_boxed_items()` method collecting the items as boxed
+///   trait objects.
+///
+/// The following set of flags are accepted to opt into additional generated code. Flags
+/// can also be given a boolean value, ie. `lenient_items = true`:
+///
+/// - `lenient_items`: Generates a `Parsed` collection type (ie. `ParsedMappingRules`)
+///   which skips malformed items and reports them along their index rather than failing
+///   the whole parse. Requires the `json` feature.
+/// - `proptest`: Generates a `strategy()` function producing arbitrary collections out
+///   of the resource's `proptest::arbitrary::Arbitrary` implementation, which you must
+///   provide. Requires the `proptest` feature.
+/// - `serialize_unwrapped`: Serializes items lacking metadata (ie. those not coming
+///   from a Porta response) without wrapping them under their snake case name.
+/// - `case_insensitive_key`: Matches the collection's key ignoring its ASCII case when
+///   deserializing, ie. accepting `Mapping_Rules` for `mapping_rules`. Cannot be
+///   combined with `pagination`.
+/// - `multi_case`: Also accepts the collection's key in snake and camel case when
+///   deserializing, ie. `mappingRules` for `mapping_rules`.
+/// - `serialize_null_metadata`: Serializes metadata back rather than skipping it, with
+///   each of the metadata type's fields set to `null` for items lacking metadata, ie. to
+///   clear them on updates. Metadata under a `metadata_field` key is `null` as a whole.
+/// - `other_variant`: Adds an `Unknown` variant to `name_tag` which unrecognized wrappers
+///   deserialize to rather than failing. Accessing the tag's inner value becomes fallible
+///   and conversions into items skip unknown wrappers, as does serializing collections.
+///   Cannot be combined with `wrap_depth`.
+/// - `item_default`: Sparse items wrapped in `name_and_metadata` fall back to their
+///   `Default` implementation for missing fields, while deserializing the item on its own
+///   still requires them. Requires the item to implement `Default`, and generates a
+///   hidden `nameDefaults` remote copy of the item. Cannot be applied to generic items.
+/// - `redact`: A comma separated list of the item's fields, ie. `"token, secret"`, to
+///   mask as `***` in `name_and_metadata`'s `Debug` output.
+/// - `sort_keys`: Serializes collections with their object keys sorted, ie. for stable
+///   snapshots or ETags. Requires the `json` feature.
+/// - `skip_metadata`: Omits the metadata field from `name_and_metadata`, so that no
+///   metadata type is needed. Helpers exposing metadata use `()` in its place and never
+///   return any. Unless combined with `track_index`, `other_variant`, fields moved into
+///   metadata or generics, `plural` also gets an `as_item_slice()` view of its items,
+///   relying on `#[repr(transparent)]` wrappers.
+/// - `track_index`: Records the index of each item in the array it was deserialized
+///   from, available through `name_and_metadata`'s `source_index()`. This includes
+///   collections built by `from_ndjson()`, `from_value_array()` or `from_bytes()`, and
+///   lenient ones, where items that failed to parse still count towards the index.
+/// - `strict_items`: Generates a `Strict` prefixed `name_and_metadata` projection which
+///   nests the item under `name_snake` and its metadata under `metadata` instead of
+///   flattening them, so that unexpected fields are rejected via `deny_unknown_fields`.
+/// - `bincode`: Generates `to_bytes()` and `from_bytes()` methods round-tripping the
+///   collection, metadata included, through `bincode` for caching. Requires the
+///   `bincode` feature, the metadata to implement `Serialize`, and the item not to
+///   flatten any of its fields.
+/// - `metadata_field`: The key Porta nests the metadata under, ie. `"meta"`, when it is
+///   not returned alongside the item's fields. Cannot be combined with `metadata_when`.
+/// - `derives`: A comma separated list of the traits to derive on the generated types,
+///   ie. `"Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize"`, replacing the
+///   default `Debug`, `PartialEq`, `Clone` and serde traits. The serde traits must be
+///   kept, and traits implemented by hand due to other attributes are skipped.
+/// - `forward_serde`: Copies the item's container level `rename_all`, `bound` and
+///   `expecting` serde attributes onto `name_and_metadata`.
+/// - `serialize_metadata`: Serializes metadata back rather than skipping it, so that
+///   parsed collections round-trip. Requires the metadata to implement `Serialize`.
+/// - `no_plural`: Generates only `name_and_metadata` and `name_tag`, ie. to parse single
+///   resource responses, leaving out `plural` and everything built on top of it.
+/// - `no_plural_key`: (De)serializes collections as a bare array of wrapped items, ie.
+///   `[{ "mapping_rule": { ... } }]`, rather than under their plural snake case key.
+///   Cannot be combined with `pagination`, `case_insensitive_key`, `sort_keys`,
+///   `lenient_items` or `multi_case`.
+/// - `paginated`: Generates a `Page` suffixed envelope (ie. `MappingRulePage`) parsing
+///   the `total_count`, `per_page` and `current_page` counts Porta returns alongside the
+///   collection, and converting into the collection. Collections get a `take_page()`
+///   slicing them into such an envelope.
+/// - `metadata_catchall`: Collects the fields returned alongside the item into a
+///   `HashMap<String, serde_json::Value>` used as metadata, instead of a user provided
+///   type. Requires the `json` feature.
+/// - `deny_unknown`: Rejects wrappers carrying keys that are neither the item's nor the
+///   metadata's, via `#[serde(deny_unknown_fields)]`. Flattened metadata keeps being
+///   accepted. Cannot be combined with `metadata_catchall`, which takes every key.
+/// - `metadata_required`: Stores the metadata without an `Option`, so that parsing
+///   items lacking it fails. Conversions from bare items, ie. `From<Vec<name>>`, and
+///   `strip_metadata()` are not generated in that case.
+/// - `csv`: Generates a `to_csv()` method exporting the items, excluding metadata, as
+///   CSV rows. Requires the `csv` feature.
+/// - `seed`: Generates a `Seed` suffixed `DeserializeSeed` (ie. `MappingRulesSeed`)
+///   handing a context to a closure for each item as it gets deserialized. Cannot be
+///   combined with `pagination`, `no_plural_key` or generic items.
 ///
 /// The following set of attributes are considered internal, since you don't need
 /// to reference them directly, but you can still work with them and could potentially
 /// cause name clashes:
 ///
 /// - `name_and_metadata`: The name of the type used to deserialize a resource along its
-///                        metadata (ie. link references, timestamps, etc)
-/// - `name_tag`: The name of the type used to match on the quirky tags Porta uses.
+///   metadata (ie. link references, timestamps, etc)
+/// - `name_tag`: The name of the type used to match on the quirky tags Porta uses. It
+///   also parses single resource responses, ie. `{ "mapping_rule": { ... } }`, and
+///   converts from and, unless using `other_variant`, into items.
+///
+/// If the annotated structure has fields using `#[serde(flatten)]`, the item is not
+/// flattened again into `name_and_metadata` but nested under its `name_snake` key.
+///
+/// Fields returned by Porta which logically belong to metadata can be marked with
+/// `#[straitjacket_field(flatten_into_metadata)]`. They are removed from the item and
+/// collected in a `ExtraMetadata` type (ie. `MappingRuleExtraMetadata`) available via
+/// the `extra_metadata()` accessor of `name_and_metadata`.
+///
+/// The generated types share the visibility and the generic parameters, bounds included,
+/// of the annotated structure. Generic items don't support the attributes generating
+/// hand-written (de)serialization or exports, ie. `sort_keys` or `csv`, nor moving
+/// fields into metadata.
+///
+/// Enums are flattened into the wrapper just like structures, so their representation
+/// must deserialize from the wrapper's map, ie. being internally, adjacently or
+/// externally tagged, or untagged over structure-like variants. The attributes referring
+/// to the item's fields, ie. `id_field`, and `item_default` are not available to them.
 #[proc_macro_attribute]
 pub fn straitjacket(attr: TokenStream, item: TokenStream) -> TokenStream {
     macro_debug!("attributes: {}", attr);
     macro_debug!("item: {}", item);
 
-    // `item` is consumed by the parsing, but we need to reproduce it verbatim
-    // so we clone it here for usage later on.
-    let c = item.clone();
-
     // parse the attributes and the item this macro applies to into ASTs
     let attr_ast = parse_macro_input!(attr as syn::AttributeArgs);
-    let item_ast = parse_macro_input!(item as DeriveInput);
+    let mut item_ast = match syn::parse::<DeriveInput>(item) {
+        Ok(DeriveInput {
+            data: syn::Data::Union(data),
+            ..
+        }) => {
+            let e = syn::Error::new(
+                data.union_token.span,
+                "`#[straitjacket]` must be applied to a struct or enum, not a union",
+            );
+            return e.to_compile_error().into();
+        }
+        Ok(item_ast) => item_ast,
+        Err(e) => {
+            let e = syn::Error::new(
+                e.span(),
+                "`#[straitjacket]` must be applied to a struct or enum",
+            );
+            return e.to_compile_error().into();
+        }
+    };
+
+    // fields marked to be moved into metadata are taken out of the item, which
+    // is otherwise reproduced verbatim
+    let moved_fields = parser::take_moved_fields(&mut item_ast.data);
 
     // the item's name (ie. the struct name)
-    let name = item_ast.ident;
+    let name = item_ast.ident.clone();
 
-    // a helper structu to validate the attributes and/or provide defaults
-    let mut sjbuilder = builder::StraitJacketBuilder::new(name);
+    // flattening an item which already flattens some of its fields can confuse
+    // serde, so in that case nest the item under its snake case name instead
+    let nested_item = parser::has_flattened_fields(&item_ast.data);
 
-    // parse attributes
-    for (ident, lit) in parser::get_attributes_and_values(&attr_ast) {
-        sjbuilder = match (ident.to_string().as_str(), lit) {
-            (key, syn::Lit::Str(lit_str)) => sjbuilder.set(key, lit_str.value().as_str()),
-            _ => sjbuilder,
-        };
-    }
+    // a container level serde rename on the item hints at how Porta names it
+    let serde_rename = parser::get_serde_rename(&item_ast.attrs);
 
-    // get the final configuration
-    let sj = sjbuilder.build();
+    let sjbuilder = match configure(name, &attr_ast) {
+        Ok(sjbuilder) => sjbuilder,
+        Err(e) => {
+            // keep the item around to avoid follow-up errors at its use sites
+            let error = e.to_compile_error();
+            return TokenStream::from(quote! {
+                #item_ast
+                #error
+            });
+        }
+    };
 
-    // the `quote` macro requires in-scope local bindings
-    let name = sj.name();
-    let name_snake = sj.name_snake();
-    let name_and_metadata = sj.name_and_metadata();
-    let name_tag = sj.name_tag();
-    let plural = sj.plural();
-    let plural_snake = sj.plural_snake();
-    let metadata = sj.metadata();
-    let name_snake_s = name_snake.to_string();
-    let plural_snake_s = plural_snake.to_string();
+    // get the final configuration
+    let sj = sjbuilder
+        .default_name_snake(serde_rename)
+        .fields(parser::get_fields(&item_ast.data))
+        .moved_fields(moved_fields)
+        .serde_attrs(parser::get_serde_container_attrs(&item_ast.attrs))
+        .nested_item(nested_item)
+        .enum_item(matches!(item_ast.data, syn::Data::Enum(_)))
+        .vis(item_ast.vis.clone())
+        .generics(item_ast.generics.clone())
+        .build();
 
-    // generate code
-    let quoted_plural = quote! {
-        #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
-        pub struct #name_and_metadata {
-            #[serde(flatten)]
-            item: #name,
-            #[serde(flatten, skip_serializing)]
-            metadata: Option<#metadata>,
+    // a field level `default` is not honored by serde for flattened fields, so the
+    // wrappers deserialize the item through a remote copy of it which falls back to
+    // its `Default` implementation for missing fields, leaving the item's own alone
+    let quoted_item_defaults = if sj.item_default() && !sj.enum_item() {
+        let shadow = parser::get_serde_shadow(&item_ast, sj.name_defaults());
+        let name_s = sj.name().to_string();
+        let default_s = format!("{}::default", sj.name());
+        let serde_attrs = sj.serde_attrs();
+        quote! {
+            #[doc(hidden)]
+            #[derive(::serde::Deserialize)]
+            #[serde(remote = #name_s, default = #default_s)]
+            #(#[serde(#serde_attrs)])*
+            #shadow
         }
+    } else {
+        quote! {}
+    };
 
-        impl #name_and_metadata {
-            pub fn item(&self) -> &#name {
-                &self.item
-            }
+    let generated = expand(&sj);
+    // emit the generated code
+    TokenStream::from(quote! {
+        #item_ast
+        #quoted_item_defaults
+        #generated
+    })
+}
 
-            pub fn metadata(&self) -> Option<&#metadata> {
-                self.metadata.as_ref()
-            }
+/// Attributes relying on the item's definition, unavailable to `straitjacket_all`.
+const FIELD_ATTRIBUTES: &[&str] = &[
+    "id_field",
+    "position_field",
+    "filter_fields",
+    "redact",
+    "item_default",
+];
 
-            pub fn item_mut(&mut self) -> &mut #name {
-                &mut self.item
-            }
+/// The `straitjacket_all` macro.
+///
+/// This macro generates the same code as the `straitjacket` attribute for a list of
+/// resources declared elsewhere, each of them optionally followed by the attributes
+/// that would be passed to `straitjacket`:
+///
+/// ```example
+/// straitjacket_all! {
+///     pub MappingRule(metadata = "MyMetadata"),
+///     pub(crate) Metric(metadata = "MyMetadata", lenient_items),
+///     Metadata,
+/// }
+/// ```
+///
+/// Each resource is preceded by the visibility of its declaration, which the generated
+/// types share, so that resources without one get private types.
+///
+/// Note that since the resources' definitions are not available to this macro, items
+/// using `#[serde(flatten)]` in their fields are not detected and will need to be
+/// annotated individually. For the same reason, the attributes referring to the item's
+/// fields, ie. `id_field`, and `item_default` are not available.
+#[proc_macro]
+pub fn straitjacket_all(input: TokenStream) -> TokenStream {
+    macro_debug!("input: {}", input);
 
-            pub fn into_item(self) -> #name {
-                self.item
-            }
-        }
+    let resources = parse_macro_input!(input as parser::Resources);
 
-        #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
-        pub enum #name_tag {
-            #[serde(rename = #name_snake_s)]
-            Tag(#name_and_metadata),
+    let q = resources.iter().map(|(vis, name, attr_ast)| {
+        let sj = match configure(name.clone(), attr_ast) {
+            Ok(sjbuilder) => sjbuilder.vis(vis.clone()).build(),
+            Err(e) => return e.to_compile_error(),
+        };
+        // the item's definition, and thus its fields, is out of sight here
+        let needs_item = parser::get_attributes_and_values(attr_ast)
+            .map(|(ident, _)| ident)
+            .chain(parser::get_flags(attr_ast))
+            .find(|ident| FIELD_ATTRIBUTES.iter().any(|attribute| ident == attribute));
+        if let Some(ident) = needs_item {
+            let msg = format!(
+                "the `{}` attribute requires applying `#[straitjacket]` to the item",
+                ident
+            );
+            return syn::Error::new(ident.span(), msg).to_compile_error();
         }
+        expand(&sj)
+    });
+    proc_macro2::TokenStream::from_iter(q).into()
+}
 
-        impl #name_tag {
-            pub fn into_inner(self) -> #name_and_metadata {
-                let #name_tag::Tag(inner) = self;
-                inner
-            }
+fn configure(
+    name: proc_macro2::Ident,
+    attr_ast: &[syn::NestedMeta],
+) -> Result<builder::StraitJacketBuilder, syn::Error> {
+    // a helper structu to validate the attributes and/or provide defaults
+    let mut sjbuilder = builder::StraitJacketBuilder::new(name);
+    // all unknown keys and invalid values are reported at once
+    let mut error: Option<syn::Error> = None;
+    let mut report = |e: syn::Error| match error.as_mut() {
+        Some(error) => error.combine(e),
+        None => error = Some(e),
+    };
+    let unknown = |ident: &proc_macro2::Ident, kind: &str, valid: &[&str]| {
+        syn::Error::new(
+            ident.span(),
+            format!(
+                "unknown `straitjacket` {} `{}`, expected one of: {}",
+                kind,
+                ident,
+                valid.join(", ")
+            ),
+        )
+    };
+    // keys given a value of the wrong type, or unknown keys otherwise
+    let invalid = |ident: &proc_macro2::Ident, lit: &syn::Lit| {
+        let key = ident.to_string();
+        if builder::FLAGS.contains(&key.as_str()) {
+            syn::Error::new(
+                lit.span(),
+                format!("the `{}` flag expects a boolean, ie. `{} = true`", key, key),
+            )
+        } else if builder::ATTRIBUTES.contains(&key.as_str()) {
+            syn::Error::new(
+                lit.span(),
+                format!("the `{}` attribute expects a string literal", key),
+            )
+        } else {
+            unknown(ident, "attribute", builder::ATTRIBUTES)
+        }
+    };
 
-            pub fn get_inner(&self) -> &#name_and_metadata {
-                let #name_tag::Tag(inner) = self;
-                inner
+    // parse attributes
+    for (ident, lit) in parser::get_attributes_and_values(attr_ast) {
+        let key = ident.to_string();
+        sjbuilder = sjbuilder.span(&key, ident.span());
+        sjbuilder = match lit {
+            syn::Lit::Str(lit_str) if builder::ATTRIBUTES.contains(&key.as_str()) => {
+                let value = lit_str.value();
+                match builder::validate(&key, value.as_str()) {
+                    Ok(()) => sjbuilder.set(&key, value.as_str()),
+                    Err(msg) => {
+                        let msg = format!("invalid `{}` attribute: {}", key, msg);
+                        report(syn::Error::new(lit_str.span(), msg));
+                        Ok(sjbuilder)
+                    }
+                }
             }
+            syn::Lit::Str(lit_str) => sjbuilder.set(&key, lit_str.value().as_str()),
+            syn::Lit::Bool(lit_bool) => sjbuilder.set_bool(&key, lit_bool.value),
+            _ => Err(sjbuilder),
         }
+        .unwrap_or_else(|sjbuilder| {
+            report(invalid(ident, lit));
+            sjbuilder
+        });
+    }
 
-        #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
-        pub struct #plural {
-            #[serde(rename = #plural_snake_s)]
-            #plural_snake: Vec<#name_tag>,
+    // parse flags
+    for ident in parser::get_flags(attr_ast) {
+        sjbuilder = sjbuilder
+            .span(&ident.to_string(), ident.span())
+            .set_flag(ident.to_string().as_str())
+            .unwrap_or_else(|sjbuilder| {
+                report(unknown(ident, "flag", builder::FLAGS));
+                sjbuilder
+            });
+    }
+
+    match error {
+        Some(error) => Err(error),
+        None => Ok(sjbuilder),
+    }
+}
+
+/// Reports an error at the attribute known as `key`, or at the macro invocation if the
+/// attribute wasn't given explicitly.
+fn attribute_error<T: std::fmt::Display>(
+    sj: &StraitJacket,
+    key: &str,
+    msg: T,
+) -> proc_macro2::TokenStream {
+    syn::Error::new(sj.span(key), msg).to_compile_error()
+}
+
+/// Generates code relying on the type of one of the item's fields, if specified, or
+/// a compile error if the item lacks such a field.
+fn with_field_type<F>(
+    sj: &StraitJacket,
+    attribute: &str,
+    field: Option<&proc_macro2::Ident>,
+    f: F,
+) -> proc_macro2::TokenStream
+where
+    F: FnOnce(&proc_macro2::Ident, &syn::Type) -> proc_macro2::TokenStream,
+{
+    match field {
+        Some(field) => match sj.field_type(field) {
+            Some(ty) => f(field, ty),
+            None => {
+                let msg = format!(
+                    "{} `{}` is not a field of `{}`",
+                    attribute,
+                    field,
+                    sj.name()
+                );
+                syn::Error::new(field.span(), msg).to_compile_error()
+            }
+        },
+        None => quote! {},
+    }
+}
+
+fn expand(sj: &StraitJacket) -> proc_macro2::TokenStream {
+    // the `quote` macro requires in-scope local bindings
+    let name_snake_s = sj.name_snake();
+    let plural_snake = sj.plural_snake();
+    let item_field = sj.item_field();
+    let tag_variant = sj.tag_variant();
+    let vis = sj.vis();
+
+    // generic items spell out their parameters on every generated type, through a
+    // turbofish so that the same tokens work in both type and expression positions
+    let generics = sj.generics();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let where_preds = where_clause
+        .iter()
+        .flat_map(|where_clause| where_clause.predicates.iter())
+        .collect::<Vec<_>>();
+    let turbofish = ty_generics.as_turbofish();
+    // items borrowing from the input need every wrapping field to borrow as well
+    let borrow_serde = if generics.lifetimes().next().is_some() {
+        quote! { #[serde(borrow)] }
+    } else {
+        quote! {}
+    };
+    let (name, name_and_metadata, name_tag, plural) = (
+        sj.name(),
+        sj.name_and_metadata(),
+        sj.name_tag(),
+        sj.plural(),
+    );
+    let (name_and_metadata_decl, name_tag_decl, plural_decl) = (
+        quote! { #name_and_metadata #generics #where_clause },
+        quote! { #name_tag #generics #where_clause },
+        quote! { #plural #generics #where_clause },
+    );
+    let (name, name_and_metadata, name_tag, plural) = (
+        quote! { #name #turbofish },
+        quote! { #name_and_metadata #turbofish },
+        quote! { #name_tag #turbofish },
+        quote! { #plural #turbofish },
+    );
+    // impls needing an extra parameter besides the item's, keeping lifetimes first
+    let generics_with = |param: syn::GenericParam| {
+        let mut generics = generics.clone();
+        match param {
+            syn::GenericParam::Lifetime(_) => generics.params.insert(0, param),
+            _ => generics.params.push(param),
+        }
+        generics
+    };
+    let lifetime_generics = generics_with(syn::parse_quote! { '__sj });
+    let (lifetime_impl_generics, _, _) = lifetime_generics.split_for_impl();
+    let array_generics = generics_with(syn::parse_quote! { const N: usize });
+    let (array_impl_generics, _, _) = array_generics.split_for_impl();
+
+    // items without metadata drop the field altogether, with `()` standing in for the
+    // metadata type in the signatures of the generated helpers
+    let skip_metadata_conflict = [
+        ("serialize_null_metadata", sj.serialize_null_metadata()),
+        ("metadata_when", sj.metadata_when().is_some()),
+        ("metadata_rename_all", sj.metadata_rename_all().is_some()),
+        ("metadata_field", sj.metadata_field().is_some()),
+        ("metadata_required", sj.metadata_required()),
+        ("metadata_catchall", sj.metadata_catchall()),
+    ]
+    .iter()
+    .find(|(_, set)| sj.skip_metadata() && *set)
+    .map(|(attribute, _)| *attribute);
+    if let Some(attribute) = skip_metadata_conflict {
+        let msg = format!(
+            "the `skip_metadata` attribute cannot be combined with `{}`",
+            attribute
+        );
+        return attribute_error(sj, attribute, msg);
+    }
+    // hand-written (de)serialization helpers and the optional exports aren't generic
+    let generics_conflict = [
+        ("sort_keys", sj.sort_keys()),
+        ("case_insensitive_key", sj.case_insensitive_key()),
+        ("other_variant", sj.other_variant()),
+        ("wrap_depth", sj.wrap_depth() > 1),
+        ("serialize_unwrapped", sj.serialize_unwrapped()),
+        ("track_index", sj.track_index()),
+        ("serialize_null_metadata", sj.serialize_null_metadata()),
+        ("metadata_rename_all", sj.metadata_rename_all().is_some()),
+        ("metadata_when", sj.metadata_when().is_some()),
+        ("redact", !sj.redact().is_empty()),
+        ("lenient_items", sj.lenient_items()),
+        ("strict_items", sj.strict_items()),
+        ("resource", sj.resource().is_some()),
+        ("trait_object", sj.trait_object().is_some()),
+        ("proptest", sj.proptest()),
+        ("csv", sj.csv()),
+        ("bincode", sj.bincode()),
+        ("item_default", sj.item_default()),
+        ("seed", sj.seed()),
+    ]
+    .iter()
+    .find(|(_, set)| !sj.generics().params.is_empty() && *set)
+    .map(|(attribute, _)| *attribute);
+    if let Some(attribute) = generics_conflict {
+        let msg = format!(
+            "the `{}` attribute cannot be applied to a generic item",
+            attribute
+        );
+        return attribute_error(sj, attribute, msg);
+    }
+    if !sj.generics().params.is_empty() && !sj.moved_fields().is_empty() {
+        return syn::Error::new_spanned(
+            &sj.moved_fields()[0],
+            "fields of a generic item cannot be flattened into its metadata",
+        )
+        .to_compile_error();
+    }
+    // attributes generating code for collections, which are not generated at all
+    let no_plural_conflict = [
+        ("no_plural_key", sj.no_plural_key()),
+        ("paginated", sj.paginated()),
+        ("pagination", sj.pagination().is_some()),
+        ("lenient_items", sj.lenient_items()),
+        ("strict_items", sj.strict_items()),
+        ("serialize_unwrapped", sj.serialize_unwrapped()),
+        ("case_insensitive_key", sj.case_insensitive_key()),
+        ("multi_case", sj.multi_case()),
+        ("sort_keys", sj.sort_keys()),
+        ("track_index", sj.track_index()),
+        ("id_field", sj.id_field().is_some()),
+        ("position_field", sj.position_field().is_some()),
+        ("filter_fields", !sj.filter_fields().is_empty()),
+        ("resource", sj.resource().is_some()),
+        ("straitjacketed", sj.straitjacketed().is_some()),
+        ("trait_object", sj.trait_object().is_some()),
+        ("no_from", !sj.no_from().is_empty()),
+        ("proptest", sj.proptest()),
+        ("csv", sj.csv()),
+        ("bincode", sj.bincode()),
+        ("seed", sj.seed()),
+    ]
+    .iter()
+    .find(|(_, set)| sj.no_plural() && *set)
+    .map(|(attribute, _)| *attribute);
+    if let Some(attribute) = no_plural_conflict {
+        let msg = format!(
+            "the `no_plural` attribute cannot be combined with `{}`",
+            attribute
+        );
+        return attribute_error(sj, attribute, msg);
+    }
+    // enums lack the named fields these attributes refer to
+    let enum_conflict = [
+        ("id_field", sj.id_field().is_some()),
+        ("position_field", sj.position_field().is_some()),
+        ("redact", !sj.redact().is_empty()),
+        ("filter_fields", !sj.filter_fields().is_empty()),
+        ("item_default", sj.item_default()),
+    ]
+    .iter()
+    .find(|(_, set)| sj.enum_item() && *set)
+    .map(|(attribute, _)| *attribute);
+    if let Some(attribute) = enum_conflict {
+        let msg = format!("the `{}` attribute cannot be applied to an enum", attribute);
+        return attribute_error(sj, attribute, msg);
+    }
+    // required metadata rules out building items without it, or nulling it out
+    let metadata_required_conflict = [
+        ("serialize_null_metadata", sj.serialize_null_metadata()),
+        ("metadata_when", sj.metadata_when().is_some()),
+        ("metadata_rename_all", sj.metadata_rename_all().is_some()),
+        ("strict_items", sj.strict_items()),
+        ("proptest", sj.proptest()),
+        ("bincode", sj.bincode()),
+    ]
+    .iter()
+    .find(|(_, set)| sj.metadata_required() && *set)
+    .map(|(attribute, _)| *attribute);
+    if let Some(attribute) = metadata_required_conflict {
+        let msg = format!(
+            "the `metadata_required` attribute cannot be combined with `{}`",
+            attribute
+        );
+        return attribute_error(sj, attribute, msg);
+    }
+    // metadata collected in a map rather than a user provided type can't be traced
+    let metadata_catchall_conflict = [
+        ("serialize_null_metadata", sj.serialize_null_metadata()),
+        ("metadata_when", sj.metadata_when().is_some()),
+        ("metadata_rename_all", sj.metadata_rename_all().is_some()),
+        ("deny_unknown", sj.deny_unknown()),
+    ]
+    .iter()
+    .find(|(_, set)| sj.metadata_catchall() && *set)
+    .map(|(attribute, _)| *attribute);
+    if let Some(attribute) = metadata_catchall_conflict {
+        let msg = format!(
+            "the `metadata_catchall` attribute cannot be combined with `{}`",
+            attribute
+        );
+        return attribute_error(sj, attribute, msg);
+    }
+    if sj.other_variant() && sj.tag_variant() == "Unknown" {
+        return attribute_error(
+            sj,
+            "tag_variant",
+            "the `tag_variant` attribute clashes with `other_variant`'s `Unknown`",
+        );
+    }
+    #[cfg(not(feature = "json"))]
+    {
+        if sj.metadata_catchall() {
+            return attribute_error(
+                sj,
+                "metadata_catchall",
+                "the `metadata_catchall` attribute requires the `json` feature",
+            );
+        }
+    }
+    let metadata = if sj.skip_metadata() {
+        quote! { () }
+    } else if sj.metadata_catchall() {
+        quote! { ::std::collections::HashMap<String, ::serde_json::Value> }
+    } else {
+        let metadata = sj.metadata();
+        quote! { #metadata }
+    };
+    // accesses to the metadata of a `name_and_metadata` binding
+    let metadata_of = |inner: proc_macro2::TokenStream| {
+        if sj.skip_metadata() {
+            quote! { None::<()> }
+        } else if sj.metadata_required() {
+            quote! { Some(#inner.metadata) }
+        } else {
+            quote! { #inner.metadata }
+        }
+    };
+    let metadata_init = if sj.skip_metadata() {
+        quote! {}
+    } else {
+        quote! { metadata: None, }
+    };
+    // raw identifiers keep their `r#` prefix when stringified, but Porta's keys don't have it
+    let plural_snake_s = plural_snake
+        .to_string()
+        .trim_start_matches("r#")
+        .to_string();
+    // alternative casings of the collection's key accepted when deserializing
+    let key_aliases = if sj.multi_case() {
+        use inflector::Inflector;
+
+        let mut aliases = vec![
+            plural_snake_s.to_snake_case(),
+            plural_snake_s.to_camel_case(),
+        ];
+        aliases.dedup();
+        aliases.retain(|alias| *alias != plural_snake_s);
+        aliases
+    } else {
+        Vec::new()
+    };
+    let items_alias_serde = quote! { #(#[serde(alias = #key_aliases)])* };
+    let item_serde = if sj.nested_item() {
+        quote! { #[serde(rename = #name_snake_s)] }
+    } else {
+        quote! { #[serde(flatten)] }
+    };
+    // sparse items are filled in from their `Default` by the remote copy of the item
+    let item_defaults_serde = if sj.item_default() {
+        let deserialize_with = format!("{}::deserialize", sj.name_defaults());
+        quote! { #[serde(deserialize_with = #deserialize_with)] }
+    } else {
+        quote! {}
+    };
+    let item_serde = quote! {
+        #item_serde
+        #item_defaults_serde
+    };
+
+    // items wrapped more than once under their snake case name are peeled via
+    // a helper layer type when (de)serializing the tag
+    let (tag_serde, quoted_layers) = if sj.wrap_depth() > 1 {
+        let layer = quote::format_ident!("{}Layer", sj.name_tag());
+        let serialize_with = format!("{}::serialize_wrapped", sj.name_tag());
+        let deserialize_with = format!("{}::deserialize_wrapped", sj.name_tag());
+        let (layered_ty, layered_value, layered_pat) = (1..sj.wrap_depth()).fold(
+            (
+                quote! { #name_and_metadata },
+                quote! { inner },
+                quote! { inner },
+            ),
+            |(ty, value, pat), _| {
+                (
+                    quote! { #layer<#ty> },
+                    quote! { #layer { inner: #value } },
+                    quote! { #layer { inner: #pat } },
+                )
+            },
+        );
+        let layered_ref_ty = (1..sj.wrap_depth()).fold(
+            quote! { &#name_and_metadata },
+            |ty, _| quote! { #layer<#ty> },
+        );
+
+        (
+            quote! { #[serde(serialize_with = #serialize_with, deserialize_with = #deserialize_with)] },
+            quote! {
+                #[doc(hidden)]
+                #[derive(::serde::Serialize, ::serde::Deserialize)]
+                #vis struct #layer<T> {
+                    #[serde(rename = #name_snake_s)]
+                    inner: T,
+                }
+
+                impl #impl_generics #name_tag #where_clause {
+                    fn serialize_wrapped<S>(inner: &#name_and_metadata, serializer: S) -> Result<S::Ok, S::Error>
+                    where
+                        S: ::serde::Serializer,
+                    {
+                        let layered: #layered_ref_ty = #layered_value;
+                        ::serde::Serialize::serialize(&layered, serializer)
+                    }
+
+                    fn deserialize_wrapped<'de, D>(deserializer: D) -> Result<#name_and_metadata, D::Error>
+                    where
+                        D: ::serde::Deserializer<'de>,
+                    {
+                        let #layered_pat = <#layered_ty as ::serde::Deserialize>::deserialize(deserializer)?;
+                        Ok(inner)
+                    }
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // collections serializing items without metadata unwrapped, or holding placeholders
+    // of unknown wrappers which can't be written back, need custom serialization
+    let (items_serde, quoted_serialize) = if sj.serialize_unwrapped() || sj.other_variant() {
+        let serialize_with = format!("{}::serialize_items", sj.plural());
+        let serialize_tag = if sj.serialize_unwrapped() {
+            quote! {
+                match tag.try_get_inner() {
+                    Some(inner) if inner.metadata().is_none() => {
+                        seq.serialize_element(inner.item())?
+                    }
+                    _ => seq.serialize_element(tag)?,
+                }
+            }
+        } else {
+            quote! { seq.serialize_element(tag)? }
+        };
+
+        (
+            quote! { #[serde(serialize_with = #serialize_with)] },
+            quote! {
+                impl #impl_generics #plural #where_clause {
+                    fn serialize_items<S>(items: &[#name_tag], serializer: S) -> Result<S::Ok, S::Error>
+                    where
+                        S: ::serde::Serializer,
+                    {
+                        use ::serde::ser::SerializeSeq;
+
+                        let known = items.iter().filter(|tag| tag.try_get_inner().is_some());
+                        let mut seq = serializer.serialize_seq(Some(known.clone().count()))?;
+                        for tag in known {
+                            #serialize_tag
+                        }
+                        seq.end()
+                    }
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // items optionally remembering their position in the source array
+    let (index_field, index_init, items_index_serde, index_items, index_tag, quoted_index) = if sj
+        .track_index()
+    {
+        let deserialize_with = format!("{}::deserialize_indexed", sj.plural());
+
+        (
+            quote! {
+                #[serde(skip)]
+                source_index: usize,
+            },
+            quote! { source_index: 0, },
+            quote! { #[serde(deserialize_with = #deserialize_with)] },
+            quote! { let items = #plural::index_items(items); },
+            quote! { #plural::index_tag(tag, idx) },
+            quote! {
+                impl #impl_generics #name_and_metadata #where_clause {
+                    /// The index of the item in the array it was deserialized from.
+                    pub fn source_index(&self) -> usize {
+                        self.source_index
+                    }
+                }
+
+                impl #impl_generics #plural #where_clause {
+                    fn index_items(items: Vec<#name_tag>) -> Vec<#name_tag> {
+                        items
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, tag)| #plural::index_tag(tag, i))
+                            .collect()
+                    }
+
+                    fn index_tag(mut tag: #name_tag, i: usize) -> #name_tag {
+                        if let Some(inner) = tag.try_get_inner_mut() {
+                            inner.source_index = i;
+                        }
+                        tag
+                    }
+
+                    fn deserialize_indexed<'de, D>(deserializer: D) -> Result<Vec<#name_tag>, D::Error>
+                    where
+                        D: ::serde::Deserializer<'de>,
+                    {
+                        <Vec<#name_tag> as ::serde::Deserialize>::deserialize(deserializer)
+                            .map(#plural::index_items)
+                    }
+                }
+            },
+        )
+    } else {
+        (
+            quote! {},
+            quote! {},
+            quote! {},
+            quote! {},
+            quote! { tag },
+            quote! {},
+        )
+    };
+
+    // collections optionally carrying flattened pagination fields
+    let (quoted_page, page_init) = match sj.pagination() {
+        Some(pagination) => (
+            quote! {
+                #[serde(flatten)]
+                page: Option<#pagination>,
+            },
+            quote! { page: None, },
+        ),
+        None => (quote! {}, quote! {}),
+    };
+
+    // deterministic output sorting object keys, going through the derived implementation
+    #[cfg(feature = "json")]
+    let (plural_serialize, quoted_sort_keys) = if sj.sort_keys() {
+        let plural_s = sj.plural().to_string();
+
+        (
+            quote! {},
+            quote! {
+                impl #impl_generics ::serde::Serialize for #plural #where_clause {
+                    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                    where
+                        S: ::serde::Serializer,
+                    {
+                        use ::serde::ser::Error;
+                        use ::serde_json::Value;
+
+                        #[derive(::serde::Serialize)]
+                        #[serde(remote = #plural_s)]
+                        struct Unsorted {
+                            #[serde(rename = #plural_snake_s)]
+                            #items_serde
+                            #plural_snake: Vec<#name_tag>,
+                            #quoted_page
+                        }
+
+                        // rebuilt maps keep insertion order even with `preserve_order`
+                        fn sort(value: Value) -> Value {
+                            match value {
+                                Value::Object(map) => {
+                                    let mut entries = map.into_iter().collect::<Vec<_>>();
+                                    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                                    Value::Object(entries.into_iter().map(|(k, v)| (k, sort(v))).collect())
+                                }
+                                Value::Array(values) => Value::Array(values.into_iter().map(sort).collect()),
+                                other => other,
+                            }
+                        }
+
+                        let value = Unsorted::serialize(self, ::serde_json::value::Serializer)
+                            .map_err(S::Error::custom)?;
+                        sort(value).serialize(serializer)
+                    }
+                }
+            },
+        )
+    } else {
+        (quote! { ::serde::Serialize, }, quote! {})
+    };
+    #[cfg(not(feature = "json"))]
+    let (plural_serialize, quoted_sort_keys) = if sj.sort_keys() {
+        (
+            quote! { ::serde::Serialize, },
+            attribute_error(
+                sj,
+                "sort_keys",
+                "the `sort_keys` attribute requires the `json` feature",
+            ),
+        )
+    } else {
+        (quote! { ::serde::Serialize, }, quote! {})
+    };
+    let quoted_page_accessor = match sj.pagination() {
+        Some(pagination) => quote! {
+            impl #impl_generics #plural #where_clause {
+                pub fn page(&self) -> Option<&#pagination> {
+                    self.page.as_ref()
+                }
+            }
+        },
+        None => quote! {},
+    };
+
+    // collections matching their key regardless of its case need a custom impl
+    let (plural_deserialize, quoted_deserialize) = if sj.case_insensitive_key() {
+        if sj.pagination().is_some() {
+            return attribute_error(
+                sj,
+                "case_insensitive_key",
+                "the `case_insensitive_key` attribute cannot be combined with `pagination`",
+            );
+        }
+        let quoted_deserialize = quote! {
+            impl<'de> ::serde::Deserialize<'de> for #plural {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: ::serde::Deserializer<'de>,
+                {
+                    use ::serde::de::{Error, IgnoredAny, MapAccess, Visitor};
+
+                    struct PluralVisitor;
+
+                    impl<'de> Visitor<'de> for PluralVisitor {
+                        type Value = #plural;
+
+                        fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                            write!(f, "a map with a `{}` key", #plural_snake_s)
+                        }
+
+                        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+                        where
+                            A: MapAccess<'de>,
+                        {
+                            let mut items = None;
+                            while let Some(key) = map.next_key::<String>()? {
+                                if key.eq_ignore_ascii_case(#plural_snake_s)
+                                    #(|| key.eq_ignore_ascii_case(#key_aliases))*
+                                {
+                                    if items.is_some() {
+                                        return Err(A::Error::duplicate_field(#plural_snake_s));
+                                    }
+                                    items = Some(map.next_value()?);
+                                } else {
+                                    map.next_value::<IgnoredAny>()?;
+                                }
+                            }
+                            let items = items.ok_or_else(|| A::Error::missing_field(#plural_snake_s))?;
+                            #index_items
+
+                            Ok(#plural { #plural_snake: items })
+                        }
+                    }
+
+                    deserializer.deserialize_map(PluralVisitor)
+                }
+            }
+        };
+
+        (quote! {}, quoted_deserialize)
+    } else {
+        (quote! { ::serde::Deserialize }, quote! {})
+    };
+
+    // fields moved out of the item are kept in a structure alongside its metadata
+    let (quoted_extra, extra_field, extra_init) = if sj.moved_fields().is_empty() {
+        (quote! {}, quote! {}, quote! {})
+    } else {
+        let extra = quote::format_ident!("{}ExtraMetadata", sj.name());
+        let moved_fields = sj.moved_fields();
+
+        (
+            quote! {
+                #[derive(Debug, PartialEq, Clone, ::serde::Serialize, ::serde::Deserialize)]
+                #vis struct #extra {
+                    #(#moved_fields),*
+                }
+
+                impl #impl_generics #name_and_metadata #where_clause {
+                    pub fn extra_metadata(&self) -> Option<&#extra> {
+                        self.extra_metadata.as_ref()
+                    }
+                }
+            },
+            quote! {
+                #[serde(flatten, skip_serializing)]
+                extra_metadata: Option<#extra>,
+            },
+            quote! { extra_metadata: None, },
+        )
+    };
+
+    // a deserializer tracing the field names of the metadata type
+    let quoted_field_names = quote! {
+        struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+        impl<'de, 'a> Deserializer<'de> for FieldNames<'a> {
+            type Error = Error;
+
+            fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+                Err(Error::custom("not a struct"))
+            }
+
+            fn deserialize_struct<V: Visitor<'de>>(
+                self,
+                _name: &'static str,
+                fields: &'static [&'static str],
+                _visitor: V,
+            ) -> Result<V::Value, Error> {
+                *self.0 = fields;
+                Err(Error::custom("fields traced"))
+            }
+
+            ::serde::forward_to_deserialize_any! {
+                bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+                bytes byte_buf option unit unit_struct newtype_struct seq tuple
+                tuple_struct map enum identifier ignored_any
+            }
+        }
+    };
+
+    // metadata is flattened into the wrapper unless Porta nests it under its own key
+    let metadata_placement = match sj.metadata_field() {
+        Some(_) if sj.metadata_when().is_some() => {
+            return attribute_error(
+                sj,
+                "metadata_field",
+                "the `metadata_field` attribute cannot be combined with `metadata_when`",
+            );
+        }
+        Some(field) if sj.metadata_required() => quote! { rename = #field },
+        Some(field) => quote! { rename = #field, default },
+        None => quote! { flatten },
+    };
+
+    // metadata is not serialized back unless explicitly nulling out missing metadata,
+    // which requires knowing the metadata's fields, obtained by tracing the fields
+    // its `Deserialize` implementation asks for
+    let (metadata_serde, quoted_null_metadata) = if sj.serialize_null_metadata()
+        && sj.metadata_field().is_some()
+    {
+        // metadata nested under its own key is nulled out as a whole
+        (quote! { #[serde(#metadata_placement)] }, quote! {})
+    } else if sj.serialize_null_metadata() {
+        let serialize_with = format!("{}::serialize_null_metadata", sj.name_and_metadata());
+
+        (
+            quote! { #[serde(#metadata_placement, serialize_with = #serialize_with)] },
+            quote! {
+                impl #impl_generics #name_and_metadata #where_clause {
+                    fn serialize_null_metadata<S>(metadata: &Option<#metadata>, serializer: S) -> Result<S::Ok, S::Error>
+                    where
+                        S: ::serde::Serializer,
+                    {
+                        use ::serde::ser::{Serialize, SerializeMap};
+                        use ::serde::de::{value::Error, Deserialize, Deserializer, Error as _, Visitor};
+
+                        #quoted_field_names
+
+                        match metadata {
+                            Some(metadata) => metadata.serialize(serializer),
+                            None => {
+                                let mut fields: &'static [&'static str] = &[];
+                                let _ = <#metadata as ::serde::Deserialize>::deserialize(FieldNames(&mut fields));
+
+                                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                                for field in fields {
+                                    map.serialize_entry(field, &())?;
+                                }
+                                map.end()
+                            }
+                        }
+                    }
+                }
+            },
+        )
+    } else if sj.serialize_metadata() {
+        // flattened missing metadata serializes to nothing, but a named key would be null
+        let skip_missing = if sj.metadata_field().is_some() && !sj.metadata_required() {
+            quote! { , skip_serializing_if = "Option::is_none" }
+        } else {
+            quote! {}
+        };
+
+        (
+            quote! { #[serde(#metadata_placement #skip_missing)] },
+            quote! {},
+        )
+    } else {
+        (
+            quote! { #[serde(#metadata_placement, skip_serializing)] },
+            quote! {},
+        )
+    };
+
+    // metadata keys sent in a different case than the metadata type's fields are
+    // mapped back to them, since flattening doesn't allow renaming the inner fields
+    #[cfg(feature = "json")]
+    let (metadata_rename_serde, quoted_metadata_rename) = match sj.metadata_rename_all() {
+        Some(_) if sj.metadata_when().is_some() => (
+            quote! {},
+            attribute_error(
+                sj,
+                "metadata_rename_all",
+                "the `metadata_rename_all` attribute cannot be combined with `metadata_when`",
+            ),
+        ),
+        Some(rule) => {
+            let rename = match rule {
+                "lowercase" | "snake_case" => Some(quote! { field.to_string() }),
+                "UPPERCASE" | "SCREAMING_SNAKE_CASE" => Some(quote! { field.to_ascii_uppercase() }),
+                "kebab-case" => Some(quote! { field.replace('_', "-") }),
+                "SCREAMING-KEBAB-CASE" => {
+                    Some(quote! { field.to_ascii_uppercase().replace('_', "-") })
+                }
+                "PascalCase" | "camelCase" => {
+                    let first = if rule == "camelCase" {
+                        quote! { first.to_ascii_lowercase() }
+                    } else {
+                        quote! { first.to_ascii_uppercase() }
+                    };
+
+                    Some(quote! {
+                        field
+                            .split('_')
+                            .enumerate()
+                            .flat_map(|(i, word)| {
+                                let mut chars = word.chars();
+                                let first = chars.next().map(|first| {
+                                    if i == 0 { #first } else { first.to_ascii_uppercase() }
+                                });
+                                first.into_iter().chain(chars)
+                            })
+                            .collect::<String>()
+                    })
+                }
+                _ => None,
+            };
+
+            match rename {
+                Some(rename) => {
+                    let deserialize_with =
+                        format!("{}::deserialize_renamed_metadata", sj.name_and_metadata());
+
+                    (
+                        quote! { #[serde(deserialize_with = #deserialize_with)] },
+                        quote! {
+                            impl #impl_generics #name_and_metadata #where_clause {
+                                fn deserialize_renamed_metadata<'de, D>(deserializer: D) -> Result<Option<#metadata>, D::Error>
+                                where
+                                    D: ::serde::Deserializer<'de>,
+                                {
+                                    use ::serde::de::{value::Error, Deserialize, Deserializer, Error as _, Visitor};
+
+                                    #quoted_field_names
+
+                                    let mut fields: &'static [&'static str] = &[];
+                                    let _ = <#metadata as ::serde::Deserialize>::deserialize(FieldNames(&mut fields));
+
+                                    let mut map = ::serde_json::Map::deserialize(deserializer)?;
+                                    let renamed = fields
+                                        .iter()
+                                        .filter_map(|field| {
+                                            map.remove(&#rename).map(|value| (field.to_string(), value))
+                                        })
+                                        .collect::<::serde_json::Map<_, _>>();
+
+                                    if renamed.is_empty() {
+                                        return Ok(None);
+                                    }
+
+                                    <#metadata as ::serde::Deserialize>::deserialize(::serde_json::Value::Object(renamed))
+                                        .map(Some)
+                                        .map_err(D::Error::custom)
+                                }
+                            }
+                        },
+                    )
+                }
+                None => {
+                    let msg = format!("unsupported `metadata_rename_all` rule `{}`", rule);
+                    (quote! {}, attribute_error(sj, "metadata_rename_all", msg))
+                }
+            }
+        }
+        None => (quote! {}, quote! {}),
+    };
+    #[cfg(not(feature = "json"))]
+    let (metadata_rename_serde, quoted_metadata_rename) = match sj.metadata_rename_all() {
+        Some(_) => (
+            quote! {},
+            attribute_error(
+                sj,
+                "metadata_rename_all",
+                "the `metadata_rename_all` attribute requires the `json` feature",
+            ),
+        ),
+        None => (quote! {}, quote! {}),
+    };
+
+    // tags optionally accept unknown wrappers as placeholders, which makes
+    // accessing their inner values fallible
+    let (unknown_variant, tag_deserialize, quoted_tag) = if sj.other_variant() {
+        let quoted_deserialize = if sj.wrap_depth() > 1 {
+            attribute_error(
+                sj,
+                "other_variant",
+                "the `other_variant` attribute cannot be combined with `wrap_depth`",
+            )
+        } else {
+            quote! {
+                impl<'de> ::serde::Deserialize<'de> for #name_tag {
+                    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                    where
+                        D: ::serde::Deserializer<'de>,
+                    {
+                        use ::serde::de::{Error, IgnoredAny, MapAccess, Visitor};
+
+                        struct TagVisitor;
+
+                        impl<'de> Visitor<'de> for TagVisitor {
+                            type Value = #name_tag;
+
+                            fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                                write!(f, "a map with a single key")
+                            }
+
+                            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+                            where
+                                A: MapAccess<'de>,
+                            {
+                                let tag = match map.next_key::<String>()? {
+                                    Some(key) if key == #name_snake_s => #name_tag::#tag_variant(map.next_value()?),
+                                    Some(_) => {
+                                        map.next_value::<IgnoredAny>()?;
+                                        #name_tag::Unknown
+                                    }
+                                    None => return Err(A::Error::invalid_length(0, &self)),
+                                };
+                                while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+
+                                Ok(tag)
+                            }
+                        }
+
+                        deserializer.deserialize_map(TagVisitor)
+                    }
+                }
+            }
+        };
+
+        (
+            quote! { Unknown, },
+            quote! {},
+            quote! {
+                #quoted_deserialize
+
+                impl #impl_generics #name_tag #where_clause {
+                    pub fn into_inner(self) -> Option<#name_and_metadata> {
+                        self.try_into_inner()
+                    }
+
+                    pub fn get_inner(&self) -> Option<&#name_and_metadata> {
+                        self.try_get_inner()
+                    }
+
+                    pub fn try_into_inner(self) -> Option<#name_and_metadata> {
+                        match self {
+                            #name_tag::#tag_variant(inner) => Some(inner),
+                            #name_tag::Unknown => None,
+                        }
+                    }
+
+                    pub fn try_get_inner(&self) -> Option<&#name_and_metadata> {
+                        match self {
+                            #name_tag::#tag_variant(inner) => Some(inner),
+                            #name_tag::Unknown => None,
+                        }
+                    }
+
+                    pub fn try_get_inner_mut(&mut self) -> Option<&mut #name_and_metadata> {
+                        match self {
+                            #name_tag::#tag_variant(inner) => Some(inner),
+                            #name_tag::Unknown => None,
+                        }
+                    }
+                }
+            },
+        )
+    } else {
+        (
+            quote! {},
+            quote! { ::serde::Deserialize },
+            quote! {
+                impl #impl_generics #name_tag #where_clause {
+                    pub fn into_inner(self) -> #name_and_metadata {
+                        let #name_tag::#tag_variant(inner) = self;
+                        inner
+                    }
+
+                    pub fn get_inner(&self) -> &#name_and_metadata {
+                        let #name_tag::#tag_variant(inner) = self;
+                        inner
+                    }
+
+                    pub fn try_into_inner(self) -> Option<#name_and_metadata> {
+                        Some(self.into_inner())
+                    }
+
+                    pub fn try_get_inner(&self) -> Option<&#name_and_metadata> {
+                        Some(self.get_inner())
+                    }
+
+                    pub fn try_get_inner_mut(&mut self) -> Option<&mut #name_and_metadata> {
+                        let #name_tag::#tag_variant(inner) = self;
+                        Some(inner)
+                    }
+                }
+
+                impl #impl_generics From<#name_tag> for #name_and_metadata #where_clause {
+                    fn from(tag: #name_tag) -> Self {
+                        tag.into_inner()
+                    }
+                }
+
+                impl #impl_generics From<#name_tag> for #name #where_clause {
+                    fn from(tag: #name_tag) -> Self {
+                        tag.into_inner().into_item()
+                    }
+                }
+            },
+        )
+    };
+
+    // metadata conditioned on a discriminator needs the whole wrapper buffered
+    let extra_init_fields = if sj.moved_fields().is_empty() {
+        quote! {}
+    } else {
+        quote! { extra_metadata, }
+    };
+    let (name_and_metadata_deserialize, quoted_metadata_when) = match sj.metadata_when() {
+        Some(_when) => {
+            #[cfg(feature = "json")]
+            let quoted_metadata_when = {
+                let when_s = _when.to_string();
+
+                quote! {
+                    impl<'de> ::serde::Deserialize<'de> for #name_and_metadata {
+                        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                        where
+                            D: ::serde::Deserializer<'de>,
+                        {
+                            use ::serde::de::Error;
+
+                            #[derive(::serde::Deserialize)]
+                            struct Unconditional {
+                                #item_serde
+                                item: #name,
+                                #extra_field
+                            }
+
+                            let value = ::serde_json::Value::deserialize(deserializer)?;
+                            let Unconditional { item, #extra_init_fields } =
+                                Unconditional::deserialize(&value).map_err(D::Error::custom)?;
+                            let metadata = match value.get(#when_s) {
+                                None
+                                | Some(::serde_json::Value::Null)
+                                | Some(::serde_json::Value::Bool(false)) => None,
+                                Some(_) => Some(<#metadata as ::serde::Deserialize>::deserialize(&value).map_err(D::Error::custom)?),
+                            };
+
+                            Ok(#name_and_metadata {
+                                #item_field: item,
+                                metadata,
+                                #extra_init_fields
+                                #index_init
+                            })
+                        }
+                    }
+                }
+            };
+            #[cfg(not(feature = "json"))]
+            let quoted_metadata_when = attribute_error(
+                sj,
+                "metadata_when",
+                "the `metadata_when` attribute requires the `json` feature",
+            );
+
+            (quote! {}, quoted_metadata_when)
+        }
+        None => (quote! { ::serde::Deserialize }, quote! {}),
+    };
+
+    // a hand-written `Debug` masking sensitive fields of the item
+    let (name_and_metadata_debug, quoted_redact) = if sj.redact().is_empty() {
+        (quote! { Debug, }, quote! {})
+    } else {
+        let name_s = sj.name().to_string();
+        let name_and_metadata_s = sj.name_and_metadata().to_string();
+        let self_metadata = metadata_of(quote! { &self });
+        let item_field_s = item_field.to_string();
+        let item_fields = sj.fields().iter().map(|(field, _)| {
+            let field_s = field.to_string();
+            if sj.redact().contains(field) {
+                quote! { .field(#field_s, &format_args!("***")) }
+            } else {
+                quote! { .field(#field_s, &self.0.#field) }
+            }
+        });
+        let unknown_fields = sj
+            .redact()
+            .iter()
+            .filter(|field| sj.field_type(field).is_none())
+            .map(|field| {
+                let msg = format!("redact `{}` is not a field of `{}`", field, sj.name());
+                syn::Error::new(field.span(), msg).to_compile_error()
+            });
+        let extra_debug = if sj.moved_fields().is_empty() {
+            quote! {}
+        } else {
+            quote! { .field("extra_metadata", &self.extra_metadata) }
+        };
+
+        (
+            quote! {},
+            quote! {
+                #(#unknown_fields)*
+
+                impl #impl_generics ::std::fmt::Debug for #name_and_metadata #where_clause {
+                    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                        struct Redacted<'a>(&'a #name);
+
+                        impl ::std::fmt::Debug for Redacted<'_> {
+                            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                                f.debug_struct(#name_s)
+                                    #(#item_fields)*
+                                    .finish()
+                            }
+                        }
+
+                        f.debug_struct(#name_and_metadata_s)
+                            .field(#item_field_s, &Redacted(&self.#item_field))
+                            .field("metadata", &#self_metadata)
+                            #extra_debug
+                            .finish()
+                    }
+                }
+            },
+        )
+    };
+
+    let metadata_ty = if sj.metadata_required() {
+        quote! { #metadata }
+    } else {
+        quote! { Option<#metadata> }
+    };
+    let metadata_field = if sj.skip_metadata() {
+        quote! {}
+    } else {
+        quote! {
+            #metadata_serde
+            #metadata_rename_serde
+            metadata: #metadata_ty,
+        }
+    };
+    let (metadata_get, metadata_get_mut, metadata_strip) = if sj.skip_metadata() {
+        (quote! { None }, quote! { None }, quote! {})
+    } else if sj.metadata_required() {
+        (
+            quote! { Some(&self.metadata) },
+            quote! { Some(&mut self.metadata) },
+            quote! {},
+        )
+    } else {
+        (
+            quote! { self.metadata.as_ref() },
+            quote! { self.metadata.as_mut() },
+            quote! {
+                for inner in self.#plural_snake.iter_mut().filter_map(#name_tag::try_get_inner_mut) {
+                    inner.metadata = None;
+                }
+            },
+        )
+    };
+    let inner_metadata = metadata_of(quote! { inner });
+
+    // derives can be overridden, except for traits implemented by hand
+    let derives =
+        |defaults: proc_macro2::TokenStream, hand_written: &[(&str, bool)]| match sj.derives() {
+            Some(paths) => {
+                let paths = paths
+                    .iter()
+                    .filter(|path| {
+                        !hand_written
+                            .iter()
+                            .any(|(derive, set)| *set && path.is_ident(derive))
+                    })
+                    .map(|path| {
+                        if path.is_ident("Serialize") || path.is_ident("Deserialize") {
+                            quote! { ::serde::#path }
+                        } else {
+                            quote! { #path }
+                        }
+                    });
+                quote! { #(#paths),* }
+            }
+            None => defaults,
+        };
+    let name_and_metadata_derives = derives(
+        quote! { #name_and_metadata_debug PartialEq, Clone, ::serde::Serialize, #name_and_metadata_deserialize },
+        &[
+            ("Debug", !sj.redact().is_empty()),
+            ("Deserialize", sj.metadata_when().is_some()),
+        ],
+    );
+    let name_tag_derives = derives(
+        quote! { Debug, PartialEq, Clone, ::serde::Serialize, #tag_deserialize },
+        &[("Deserialize", sj.other_variant())],
+    );
+    let plural_derives = derives(
+        quote! { Debug, PartialEq, Clone, #plural_serialize #plural_deserialize },
+        &[
+            ("Serialize", plural_serialize.is_empty()),
+            ("Deserialize", plural_deserialize.is_empty()),
+            ("Default", true),
+        ],
+    );
+
+    // collections optionally (de)serialize as a bare array of tags
+    let (plural_serde, items_key_serde) = if sj.no_plural_key() {
+        let conflict = [
+            ("pagination", sj.pagination().is_some()),
+            ("case_insensitive_key", sj.case_insensitive_key()),
+            ("sort_keys", sj.sort_keys()),
+            ("lenient_items", sj.lenient_items()),
+            ("multi_case", sj.multi_case()),
+            ("seed", sj.seed()),
+        ]
+        .iter()
+        .find(|(_, set)| *set)
+        .map(|(attribute, _)| *attribute);
+        if let Some(attribute) = conflict {
+            let msg = format!(
+                "the `no_plural_key` attribute cannot be combined with `{}`",
+                attribute
+            );
+            return attribute_error(sj, attribute, msg);
+        }
+
+        (quote! { #[serde(transparent)] }, quote! {})
+    } else {
+        (
+            quote! {},
+            quote! {
+                #[serde(rename = #plural_snake_s)]
+                #items_alias_serde
+            },
+        )
+    };
+
+    // container attributes of the item optionally apply to its wrapper as well
+    let forwarded_serde = if sj.forward_serde() && !sj.serde_attrs().is_empty() {
+        let serde_attrs = sj.serde_attrs();
+        quote! { #[serde(#(#serde_attrs),*)] }
+    } else {
+        quote! {}
+    };
+
+    // flattened fields still consume their keys, so only truly unknown ones are rejected
+    let deny_unknown_serde = if sj.deny_unknown() {
+        quote! { #[serde(deny_unknown_fields)] }
+    } else {
+        quote! {}
+    };
+
+    // without metadata nor any other field, both the wrapper and the tag are laid out
+    // exactly as the item, so the tags can be viewed as a slice of items
+    let transparent = sj.skip_metadata()
+        && sj.moved_fields().is_empty()
+        && !sj.track_index()
+        && !sj.other_variant()
+        && sj.generics().params.is_empty();
+    let (transparent_repr, quoted_item_slice) = if transparent {
+        let quoted_item_slice = quote! {
+            const _: () = {
+                use ::std::mem::{align_of, size_of};
+                assert!(size_of::<#name_tag>() == size_of::<#name>());
+                assert!(align_of::<#name_tag>() == align_of::<#name>());
+            };
+
+            impl #plural {
+                /// Views the items of the collection without copying them.
+                pub fn as_item_slice(&self) -> &[#name] {
+                    let tags = self.#plural_snake.as_slice();
+                    // SAFETY: `name_and_metadata` is a `#[repr(transparent)]` struct whose
+                    // only field is the item, and `name_tag` is a `#[repr(transparent)]`
+                    // enum whose only variant holds `name_and_metadata`, so a tag has the
+                    // same size, alignment and ABI as the item (also asserted above at
+                    // compile time). A slice of tags is thus a valid slice of items of
+                    // the same length, borrowed for as long as `self`.
+                    unsafe { ::std::slice::from_raw_parts(tags.as_ptr() as *const #name, tags.len()) }
+                }
+            }
+        };
+        (quote! { #[repr(transparent)] }, quoted_item_slice)
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // conversions which may clash with the user's own, see `no_from`
+    // collections out of bare items, also built by strategies regardless of `no_from`
+    let plural_from_items = quote! {
+        #plural {
+            #plural_snake: items
+                .into_iter()
+                .map(|item| #name_tag::#tag_variant(#name_and_metadata {
+                    #item_field: item,
+                    #metadata_init
+                    #extra_init
+                    #index_init
+                })).collect::<Vec<_>>(),
+            #page_init
+        }
+    };
+    let quoted_vec_to_plural = if sj.generates_from("vec_to_plural") {
+        quote! {
+            impl #impl_generics From<Vec<#name>> for #plural #where_clause {
+                fn from(items: Vec<#name>) -> Self {
+                    #plural_from_items
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let quoted_plural_to_vec = if sj.generates_from("plural_to_vec") {
+        quote! {
+            impl #impl_generics From<#plural> for Vec<#name> #where_clause {
+                fn from(mrs: #plural) -> Self {
+                    mrs.#plural_snake.into_iter()
+                        .filter_map(#name_tag::try_into_inner)
+                        .map(#name_and_metadata::into_item)
+                        .collect()
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let quoted_plural_to_vec_and_metadata = if sj.generates_from("plural_to_vec_and_metadata") {
+        quote! {
+            impl #impl_generics From<#plural> for Vec<#name_and_metadata> #where_clause {
+                fn from(mrs: #plural) -> Self {
+                    mrs.#plural_snake.into_iter()
+                        .filter_map(#name_tag::try_into_inner)
+                        .collect()
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // conversions from items lacking metadata, unavailable when it is required
+    let (quoted_bare_tag, quoted_bare_items) = if sj.metadata_required() {
+        (quote! {}, quote! {})
+    } else {
+        let quoted_bare_tag = quote! {
+            impl #impl_generics From<#name> for #name_tag #where_clause {
+                fn from(item: #name) -> Self {
+                    #name_tag::#tag_variant(#name_and_metadata {
+                        #item_field: item,
+                        #metadata_init
+                        #extra_init
+                        #index_init
+                    })
+                }
+            }
+        };
+        let quoted_bare_items = quote! {
+            impl #impl_generics #plural #where_clause {
+                pub fn strip_metadata(&mut self) {
+                    #metadata_strip
+                }
+
+                pub fn pad_to(&mut self, n: usize, mut f: impl FnMut() -> #name) {
+                    self.#plural_snake.resize_with(n.max(self.#plural_snake.len()), || {
+                        #name_tag::#tag_variant(#name_and_metadata {
+                            #item_field: f(),
+                            #metadata_init
+                            #extra_init
+                            #index_init
+                        })
+                    })
+                }
+            }
+
+            #quoted_vec_to_plural
+
+            impl #impl_generics ::std::iter::FromIterator<#name> for #plural #where_clause {
+                fn from_iter<__SjIter: IntoIterator<Item = #name>>(iter: __SjIter) -> Self {
+                    #plural {
+                        #plural_snake: iter.into_iter().map(#name_tag::from).collect(),
+                        #page_init
+                    }
+                }
+            }
+
+            impl #lifetime_impl_generics ::std::iter::FromIterator<&'__sj #name> for #plural
+            where
+                #(#where_preds,)*
+                #name: Clone,
+            {
+                fn from_iter<__SjIter: IntoIterator<Item = &'__sj #name>>(iter: __SjIter) -> Self {
+                    iter.into_iter().cloned().collect()
+                }
+            }
+        };
+        (quoted_bare_tag, quoted_bare_items)
+    };
+
+    // generate code
+    let quoted_singular = quote! {
+        #[derive(#name_and_metadata_derives)]
+        #transparent_repr
+        #forwarded_serde
+        #deny_unknown_serde
+        #vis struct #name_and_metadata_decl {
+            #item_serde
+            #borrow_serde
+            #item_field: #name,
+            #metadata_field
+            #extra_field
+            #index_field
+        }
+
+        #quoted_null_metadata
+
+        #quoted_metadata_rename
+
+        #quoted_metadata_when
+
+        #quoted_redact
+
+        #quoted_extra
+
+        impl #impl_generics #name_and_metadata #where_clause {
+            pub fn item(&self) -> &#name {
+                &self.#item_field
+            }
+
+            pub fn metadata(&self) -> Option<&#metadata> {
+                #metadata_get
+            }
+
+            pub fn item_mut(&mut self) -> &mut #name {
+                &mut self.#item_field
+            }
+
+            pub fn metadata_mut(&mut self) -> Option<&mut #metadata> {
+                #metadata_get_mut
+            }
+
+            pub fn into_item(self) -> #name {
+                self.#item_field
+            }
+        }
+
+        /// Wraps an item as in single resource responses, ie. `{ "mapping_rule": { ... } }`.
+        impl #impl_generics From<#name_and_metadata> for #name_tag #where_clause {
+            fn from(inner: #name_and_metadata) -> Self {
+                #name_tag::#tag_variant(inner)
+            }
+        }
+
+        impl #impl_generics PartialEq<#name> for #name_and_metadata
+        where
+            #(#where_preds,)*
+            #name: PartialEq,
+        {
+            fn eq(&self, other: &#name) -> bool {
+                self.#item_field == *other
+            }
+        }
+
+        /// The key Porta uses for this resource, as derived by `straitjacket`.
+        impl #impl_generics #name #where_clause {
+            pub const SNAKE: &'static str = #name_snake_s;
+        }
+
+        #[derive(#name_tag_derives)]
+        #transparent_repr
+        #vis enum #name_tag_decl {
+            #[serde(rename = #name_snake_s)]
+            #tag_serde
+            #borrow_serde
+            #tag_variant(#name_and_metadata),
+            #unknown_variant
+        }
+
+        #quoted_layers
+
+        #quoted_tag
+
+        #quoted_bare_tag
+    };
+
+    let quoted_plural = quote! {
+        impl #impl_generics #name #where_clause {
+            pub const PLURAL_SNAKE: &'static str = #plural_snake_s;
+        }
+
+        #[derive(#plural_derives)]
+        #plural_serde
+        #vis struct #plural_decl {
+            #items_key_serde
+            #items_serde
+            #items_index_serde
+            #borrow_serde
+            #plural_snake: Vec<#name_tag>,
+            #quoted_page
+        }
+
+        #quoted_index
+
+        impl #impl_generics #plural #where_clause {
+            pub const fn new_empty() -> Self {
+                #plural { #plural_snake: Vec::new(), #page_init }
+            }
+
+            pub fn collection_key() -> &'static str {
+                #plural_snake_s
+            }
+
+            pub fn len(&self) -> usize {
+                self.#plural_snake.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.#plural_snake.is_empty()
+            }
+
+            pub fn get_inner(&self) -> &Vec<#name_tag> {
+                &self.#plural_snake
+            }
+
+            pub fn iter(&self) -> impl Iterator<Item = &#name> {
+                self.#plural_snake
+                    .iter()
+                    .filter_map(#name_tag::try_get_inner)
+                    .map(#name_and_metadata::item)
+            }
+
+            pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut #name> {
+                self.#plural_snake
+                    .iter_mut()
+                    .filter_map(#name_tag::try_get_inner_mut)
+                    .map(#name_and_metadata::item_mut)
+            }
+
+            /// Unwraps the collection into its items, dropping any metadata.
+            pub fn into_items(self) -> Vec<#name> {
+                self.#plural_snake
+                    .into_iter()
+                    .filter_map(#name_tag::try_into_inner)
+                    .map(#name_and_metadata::into_item)
+                    .collect()
+            }
+
+            /// Borrows the items in the collection without cloning them.
+            pub fn items(&self) -> Vec<&#name> {
+                self.iter().collect()
+            }
+
+            /// Splits the collection into parallel vectors of items and their metadata.
+            pub fn into_parts(self) -> (Vec<#name>, Vec<Option<#metadata>>) {
+                self.#plural_snake
+                    .into_iter()
+                    .filter_map(#name_tag::try_into_inner)
+                    .map(|inner| (inner.#item_field, #inner_metadata))
+                    .unzip()
+            }
+
+            /// Returns borrowed batches of `n` items, ie. for bulk submissions, with only
+            /// the last one possibly holding fewer. Unknown wrappers are skipped.
+            ///
+            /// Panics if `n` is 0.
+            pub fn chunks(&self, n: usize) -> impl Iterator<Item = Vec<&#name>> {
+                let items = self.iter().collect::<Vec<_>>();
+                items.chunks(n).map(<[_]>::to_vec).collect::<Vec<_>>().into_iter()
+            }
+
+            pub fn reserve(&mut self, n: usize) {
+                self.#plural_snake.reserve(n)
+            }
+
+            pub fn shrink_to_fit(&mut self) {
+                self.#plural_snake.shrink_to_fit()
+            }
+
+            pub fn take(&mut self) -> #plural {
+                ::std::mem::take(self)
+            }
+
+            pub fn into_non_empty(self) -> Option<#plural> {
+                if self.#plural_snake.is_empty() {
+                    None
+                } else {
+                    Some(self)
+                }
+            }
         }
 
-        impl #plural {
-            pub fn get_inner(&self) -> &Vec<#name_tag> {
+        // unlike a derived implementation, this doesn't require the item's parameters
+        // to implement `Default`
+        impl #impl_generics Default for #plural #where_clause {
+            fn default() -> Self {
+                Self::new_empty()
+            }
+        }
+
+        /// Derefs to the tags wrapping each item rather than to the items themselves.
+        impl #impl_generics ::std::ops::Deref for #plural #where_clause {
+            type Target = Vec<#name_tag>;
+
+            fn deref(&self) -> &Self::Target {
                 &self.#plural_snake
             }
         }
 
-        impl From<Vec<#name>> for #plural {
-            fn from(mrvec: Vec<#name>) -> Self {
-                #plural {
-                    #plural_snake: mrvec
+        impl #impl_generics ::std::ops::DerefMut for #plural #where_clause {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.#plural_snake
+            }
+        }
+
+        impl #impl_generics AsRef<[#name_tag]> for #plural #where_clause {
+            fn as_ref(&self) -> &[#name_tag] {
+                &self.#plural_snake
+            }
+        }
+
+        impl #impl_generics AsMut<[#name_tag]> for #plural #where_clause {
+            fn as_mut(&mut self) -> &mut [#name_tag] {
+                &mut self.#plural_snake
+            }
+        }
+
+        #quoted_bare_items
+
+        #quoted_plural_to_vec_and_metadata
+
+        impl #lifetime_impl_generics From<&'__sj #plural> for Vec<(&'__sj #name, Option<&'__sj #metadata>)> #where_clause {
+            fn from(mrs: &'__sj #plural) -> Self {
+                mrs.#plural_snake.iter()
+                    .filter_map(#name_tag::try_get_inner)
+                    .map(|inner| (inner.item(), inner.metadata()))
+                    .collect()
+            }
+        }
+
+        #quoted_plural_to_vec
+
+        impl #impl_generics IntoIterator for #plural #where_clause {
+            type Item = #name;
+            type IntoIter = ::std::iter::Map<
+                ::std::iter::FilterMap<
+                    ::std::vec::IntoIter<#name_tag>,
+                    fn(#name_tag) -> Option<#name_and_metadata>,
+                >,
+                fn(#name_and_metadata) -> #name,
+            >;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.#plural_snake
+                    .into_iter()
+                    .filter_map(#name_tag::try_into_inner as fn(_) -> _)
+                    .map(#name_and_metadata::into_item as fn(_) -> _)
+            }
+        }
+
+        impl #lifetime_impl_generics IntoIterator for &'__sj #plural #where_clause {
+            type Item = &'__sj #name;
+            type IntoIter = ::std::iter::Map<
+                ::std::iter::FilterMap<
+                    ::std::slice::Iter<'__sj, #name_tag>,
+                    fn(&'__sj #name_tag) -> Option<&'__sj #name_and_metadata>,
+                >,
+                fn(&'__sj #name_and_metadata) -> &'__sj #name,
+            >;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.#plural_snake
+                    .iter()
+                    .filter_map(#name_tag::try_get_inner as fn(_) -> _)
+                    .map(#name_and_metadata::item as fn(_) -> _)
+            }
+        }
+
+        /// Like the conversion from `Vec`, gives the items back if their count doesn't match.
+        impl #array_impl_generics ::std::convert::TryFrom<#plural> for [#name; N] #where_clause {
+            type Error = Vec<#name>;
+
+            fn try_from(mrs: #plural) -> Result<Self, Self::Error> {
+                <[#name; N] as ::std::convert::TryFrom<Vec<#name>>>::try_from(mrs.into_items())
+            }
+        }
+    };
+
+    // helpers that need `serde_json` to be available to the user
+    #[cfg(feature = "json")]
+    let quoted_json = quote! {
+        impl #impl_generics #plural
+        where
+            #(#where_preds,)*
+            #plural: ::serde::Serialize + ::serde::de::DeserializeOwned,
+            #name_tag: ::serde::de::DeserializeOwned,
+        {
+            pub fn parse_items(json: &str) -> Result<Vec<#name>, ::serde_json::Error> {
+                ::serde_json::from_str::<#plural>(json).map(#plural::into_items)
+            }
+
+            pub fn from_slice(json: &[u8]) -> Result<#plural, ::serde_json::Error> {
+                ::serde_json::from_slice(json)
+            }
+
+            pub fn from_ndjson(input: &str) -> Result<#plural, ::serde_json::Error> {
+                let items = input
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(::serde_json::from_str::<#name_tag>)
+                    .collect::<Result<Vec<_>, _>>()?;
+                #index_items
+
+                Ok(#plural { #plural_snake: items, #page_init })
+            }
+
+            /// Builds a collection out of the already extracted array under its key.
+            pub fn from_value_array(items: Vec<::serde_json::Value>) -> Result<#plural, ::serde_json::Error> {
+                let items = items
+                    .into_iter()
+                    .map(::serde_json::from_value::<#name_tag>)
+                    .collect::<Result<Vec<_>, _>>()?;
+                #index_items
+
+                Ok(#plural { #plural_snake: items, #page_init })
+            }
+
+            pub fn to_writer<W: ::std::io::Write>(&self, w: W) -> Result<(), ::serde_json::Error> {
+                ::serde_json::to_writer(w, self)
+            }
+
+            /// Checks that the collection serializes into something that can be parsed back.
+            pub fn validate_serialization(&self) -> Result<(), ::serde_json::Error> {
+                let serialized = ::serde_json::to_vec(self)?;
+                ::serde_json::from_slice::<#plural>(&serialized).map(|_| ())
+            }
+
+            pub fn from_double_encoded(outer_json: &str, field: &str) -> Result<#plural, ::serde_json::Error> {
+                use ::serde::de::Error;
+
+                let outer: ::serde_json::Value = ::serde_json::from_str(outer_json)?;
+                match outer.get(field) {
+                    Some(::serde_json::Value::String(inner)) => ::serde_json::from_str(inner),
+                    Some(_) => Err(::serde_json::Error::custom(format!(
+                        "field `{}` does not contain a JSON-encoded string",
+                        field
+                    ))),
+                    None => Err(::serde_json::Error::custom(format!("missing field `{}`", field))),
+                }
+            }
+        }
+
+        impl #impl_generics ::std::convert::TryFrom<#plural> for ::serde_json::Value
+        where
+            #(#where_preds,)*
+            #plural: ::serde::Serialize,
+        {
+            type Error = ::serde_json::Error;
+
+            fn try_from(mrs: #plural) -> Result<Self, Self::Error> {
+                ::serde_json::to_value(mrs)
+            }
+        }
+
+        impl #lifetime_impl_generics ::std::convert::TryFrom<&'__sj str> for #plural
+        where
+            #(#where_preds,)*
+            #plural: ::serde::de::DeserializeOwned,
+        {
+            type Error = ::serde_json::Error;
+
+            fn try_from(json: &'__sj str) -> Result<Self, Self::Error> {
+                ::serde_json::from_str(json)
+            }
+        }
+
+        impl #impl_generics ::std::str::FromStr for #plural
+        where
+            #(#where_preds,)*
+            #plural: ::serde::de::DeserializeOwned,
+        {
+            type Err = ::serde_json::Error;
+
+            fn from_str(json: &str) -> Result<Self, Self::Err> {
+                ::serde_json::from_str(json)
+            }
+        }
+
+        impl #impl_generics ::std::fmt::Display for #plural
+        where
+            #(#where_preds,)*
+            #plural: ::serde::Serialize,
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                let json = ::serde_json::to_string_pretty(self).map_err(|_| ::std::fmt::Error)?;
+                f.write_str(&json)
+            }
+        }
+    };
+    #[cfg(not(feature = "json"))]
+    let quoted_json = quote! {};
+
+    // collections read in full from async sources, ie. response bodies
+    #[cfg(feature = "tokio")]
+    let quoted_tokio = quote! {
+        impl #impl_generics #plural
+        where
+            #(#where_preds,)*
+            #plural: ::serde::de::DeserializeOwned,
+        {
+            pub async fn from_reader<R>(mut reader: R) -> Result<#plural, ::std::io::Error>
+            where
+                R: ::tokio::io::AsyncRead + Unpin,
+            {
+                let mut json = Vec::new();
+                ::tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut json).await?;
+                ::serde_json::from_slice(&json).map_err(::std::io::Error::from)
+            }
+        }
+    };
+    #[cfg(not(feature = "tokio"))]
+    let quoted_tokio = quote! {};
+
+    // collections parsed out of Porta responses, failing on unsuccessful statuses
+    #[cfg(feature = "reqwest")]
+    let quoted_reqwest = {
+        let error = quote::format_ident!("{}ResponseError", sj.plural());
+        let status_msg = format!("{} request failed with status {{}}", sj.plural());
+        let request_msg = format!("{} request failed: {{}}", sj.plural());
+        let parse_msg = format!("{} response could not be parsed: {{}}", sj.plural());
+
+        quote! {
+            #[derive(Debug)]
+            #vis enum #error {
+                /// The response carried an unsuccessful status.
+                Status(::reqwest::StatusCode),
+                /// The response body could not be read.
+                Request(::reqwest::Error),
+                /// The response body could not be parsed.
+                Parse(::serde_json::Error),
+            }
+
+            impl ::std::fmt::Display for #error {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    match self {
+                        #error::Status(status) => write!(f, #status_msg, status),
+                        #error::Request(e) => write!(f, #request_msg, e),
+                        #error::Parse(e) => write!(f, #parse_msg, e),
+                    }
+                }
+            }
+
+            impl ::std::error::Error for #error {
+                fn source(&self) -> Option<&(dyn ::std::error::Error + 'static)> {
+                    match self {
+                        #error::Status(_) => None,
+                        #error::Request(e) => Some(e),
+                        #error::Parse(e) => Some(e),
+                    }
+                }
+            }
+
+            impl #impl_generics #plural
+            where
+                #(#where_preds,)*
+                #plural: ::serde::de::DeserializeOwned,
+            {
+                pub async fn from_response(response: ::reqwest::Response) -> Result<#plural, #error> {
+                    let status = response.status();
+                    if !status.is_success() {
+                        return Err(#error::Status(status));
+                    }
+                    let json = response.bytes().await.map_err(#error::Request)?;
+                    ::serde_json::from_slice(&json).map_err(#error::Parse)
+                }
+            }
+        }
+    };
+    #[cfg(not(feature = "reqwest"))]
+    let quoted_reqwest = quote! {};
+
+    // collections parsed skipping malformed items rather than failing
+    #[cfg(feature = "json")]
+    let quoted_lenient = {
+        let parsed = quote::format_ident!("Parsed{}", sj.plural());
+
+        quote! {
+            #[derive(Debug)]
+            #vis struct #parsed {
+                collection: #plural,
+                errors: Vec<(usize, ::serde_json::Error)>,
+            }
+
+            impl #parsed {
+                pub fn collection(&self) -> &#plural {
+                    &self.collection
+                }
+
+                pub fn errors(&self) -> &[(usize, ::serde_json::Error)] {
+                    self.errors.as_slice()
+                }
+
+                pub fn into_parts(self) -> (#plural, Vec<(usize, ::serde_json::Error)>) {
+                    (self.collection, self.errors)
+                }
+            }
+
+            impl<'de> ::serde::Deserialize<'de> for #parsed {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: ::serde::Deserializer<'de>,
+                {
+                    #[derive(::serde::Deserialize)]
+                    struct Lenient {
+                        #[serde(rename = #plural_snake_s)]
+                        #items_alias_serde
+                        items: Vec<::serde_json::Value>,
+                    }
+
+                    let lenient = Lenient::deserialize(deserializer)?;
+                    let mut items = Vec::with_capacity(lenient.items.len());
+                    let mut errors = Vec::new();
+
+                    for (idx, value) in lenient.items.into_iter().enumerate() {
+                        match ::serde_json::from_value::<#name_tag>(value) {
+                            Ok(tag) => items.push(#index_tag),
+                            Err(e) => errors.push((idx, e)),
+                        }
+                    }
+
+                    Ok(#parsed {
+                        collection: #plural { #plural_snake: items, #page_init },
+                        errors,
+                    })
+                }
+            }
+        }
+    };
+    #[cfg(not(feature = "json"))]
+    let quoted_lenient = attribute_error(
+        sj,
+        "lenient_items",
+        "the `lenient_items` attribute requires the `json` feature",
+    );
+    let quoted_lenient = if sj.lenient_items() {
+        quoted_lenient
+    } else {
+        quote! {}
+    };
+
+    // helpers relying on the field used to identify items
+    let quoted_id = with_field_type(sj, "id_field", sj.id_field(), |id_field, id_ty| {
+        let merged_metadata = if sj.metadata_required() {
+            quote! { (*metadata).clone() }
+        } else {
+            quote! { Some((*metadata).clone()) }
+        };
+        let merge_metadata = if sj.skip_metadata() {
+            quote! { let _ = other; }
+        } else {
+            quote! {
+                let by_id = other.metadata_by_id();
+                for inner in self.#plural_snake.iter_mut().filter_map(#name_tag::try_get_inner_mut) {
+                    if let Some(metadata) = by_id.get(&inner.#item_field.#id_field) {
+                        inner.metadata = #merged_metadata;
+                    }
+                }
+            }
+        };
+
+        quote! {
+            impl #impl_generics #plural #where_clause {
+                pub fn metadata_by_id(&self) -> ::std::collections::HashMap<#id_ty, &#metadata> {
+                    self.#plural_snake
+                        .iter()
+                        .filter_map(#name_tag::try_get_inner)
+                        .filter_map(|inner| {
+                            inner.metadata().map(|metadata| (inner.item().#id_field.clone(), metadata))
+                        })
+                        .collect()
+                }
+
+                /// Looks up an item by id in O(log n) time.
+                ///
+                /// The collection must be sorted by id and contain no unknown items, otherwise
+                /// the result is unspecified.
+                /// See `slice::binary_search` for the meaning of the returned value.
+                pub fn binary_search_by_id(&self, id: &#id_ty) -> Result<usize, usize> {
+                    self.#plural_snake
+                        .binary_search_by(|tag| {
+                            tag.try_get_inner()
+                                .map_or(::std::cmp::Ordering::Less, |inner| inner.item().#id_field.cmp(id))
+                        })
+                }
+
+                /// Copies the metadata of the items in `other` into the items sharing their id.
+                pub fn merge_metadata_from(&mut self, other: &#plural) {
+                    #merge_metadata
+                }
+
+                /// Checks that no two items share an id, returning each duplicated id otherwise.
+                pub fn verify_unique_ids(&self) -> Result<(), Vec<#id_ty>> {
+                    let mut seen = ::std::collections::HashSet::new();
+                    let mut duplicated = Vec::new();
+
+                    for inner in self.#plural_snake.iter().filter_map(#name_tag::try_get_inner) {
+                        let id = &inner.item().#id_field;
+                        if !seen.insert(id) && !duplicated.contains(id) {
+                            duplicated.push(id.clone());
+                        }
+                    }
+
+                    if duplicated.is_empty() {
+                        Ok(())
+                    } else {
+                        Err(duplicated)
+                    }
+                }
+            }
+        }
+    });
+
+    // helpers filtering items by string fields
+    let quoted_filters = sj.filter_fields().iter().map(|filter_field| {
+        with_field_type(sj, "filter_fields", Some(filter_field), |field, _ty| {
+            let method =
+                quote::format_ident!("with_{}", field.to_string().trim_start_matches("r#"));
+
+            quote! {
+                impl #impl_generics #plural #where_clause {
+                    pub fn #method(&self, v: &str) -> Vec<&#name> {
+                        self.#plural_snake
+                            .iter()
+                            .filter_map(#name_tag::try_get_inner)
+                            .map(#name_and_metadata::item)
+                            .filter(|item| item.#field == v)
+                            .collect()
+                    }
+                }
+            }
+        })
+    });
+    let quoted_filters = quote! { #(#quoted_filters)* };
+
+    // helpers relying on the field used to order items
+    let quoted_position = with_field_type(
+        sj,
+        "position_field",
+        sj.position_field(),
+        |position_field, position_ty| {
+            quote! {
+                impl #impl_generics #plural #where_clause {
+                    pub fn take_until_position(&self, max: #position_ty) -> Vec<&#name> {
+                        self.#plural_snake
+                            .iter()
+                            .filter_map(#name_tag::try_get_inner)
+                            .map(#name_and_metadata::item)
+                            .take_while(|item| item.#position_field <= max)
+                            .collect()
+                    }
+                }
+            }
+        },
+    );
+
+    // strategies generating collections out of arbitrary items
+    #[cfg(feature = "proptest")]
+    let quoted_proptest = quote! {
+        impl #impl_generics #plural #where_clause {
+            pub fn strategy() -> impl ::proptest::strategy::Strategy<Value = #plural> {
+                use ::proptest::strategy::Strategy;
+
+                ::proptest::collection::vec(
+                    ::proptest::arbitrary::any::<#name>(),
+                    ::proptest::collection::SizeRange::default(),
+                )
+                .prop_map(|items| #plural_from_items)
+            }
+        }
+    };
+    #[cfg(not(feature = "proptest"))]
+    let quoted_proptest = attribute_error(
+        sj,
+        "proptest",
+        "the `proptest` attribute requires the `proptest` feature",
+    );
+    let quoted_proptest = if sj.proptest() {
+        quoted_proptest
+    } else {
+        quote! {}
+    };
+
+    // envelopes carrying Porta's item counts alongside a collection
+    let quoted_paginated = if !sj.paginated() {
+        quote! {}
+    } else if sj.no_plural_key() {
+        attribute_error(
+            sj,
+            "paginated",
+            "the `paginated` attribute cannot be combined with `no_plural_key`",
+        )
+    } else {
+        let page = quote::format_ident!("{}Page", sj.name());
+        let page_decl = quote! { #page #generics #where_clause };
+        let page = quote! { #page #turbofish };
+
+        quote! {
+            #[derive(Debug, PartialEq, Clone, ::serde::Serialize, ::serde::Deserialize)]
+            #vis struct #page_decl {
+                #items_key_serde
+                #items_index_serde
+                #borrow_serde
+                #plural_snake: Vec<#name_tag>,
+                #[serde(default, skip_serializing_if = "Option::is_none")]
+                total_count: Option<u64>,
+                #[serde(default, skip_serializing_if = "Option::is_none")]
+                per_page: Option<u64>,
+                #[serde(default, skip_serializing_if = "Option::is_none")]
+                current_page: Option<u64>,
+            }
+
+            impl #impl_generics #page #where_clause {
+                pub fn total_count(&self) -> Option<u64> {
+                    self.total_count
+                }
+
+                pub fn per_page(&self) -> Option<u64> {
+                    self.per_page
+                }
+
+                pub fn current_page(&self) -> Option<u64> {
+                    self.current_page
+                }
+
+                pub fn get_inner(&self) -> &Vec<#name_tag> {
+                    &self.#plural_snake
+                }
+            }
+
+            impl #impl_generics #plural
+            where
+                #(#where_preds,)*
+                #name_tag: Clone,
+            {
+                /// Clones the items of the 1-based `page` into an envelope counting the
+                /// collection's items, ie. to serve it from a mock server.
+                pub fn take_page(&self, page: u64, per_page: u64) -> #page {
+                    let skip = page.saturating_sub(1).saturating_mul(per_page);
+                    #page {
+                        #plural_snake: self.#plural_snake
+                            .iter()
+                            .skip(skip as usize)
+                            .take(per_page as usize)
+                            .cloned()
+                            .collect(),
+                        total_count: Some(self.#plural_snake.len() as u64),
+                        per_page: Some(per_page),
+                        current_page: Some(page),
+                    }
+                }
+            }
+
+            impl #impl_generics From<#page> for #plural #where_clause {
+                fn from(page: #page) -> Self {
+                    #plural { #plural_snake: page.#plural_snake, #page_init }
+                }
+            }
+
+            impl #impl_generics IntoIterator for #page #where_clause {
+                type Item = #name;
+                type IntoIter = <#plural as IntoIterator>::IntoIter;
+
+                fn into_iter(self) -> Self::IntoIter {
+                    #plural::from(self).into_iter()
+                }
+            }
+
+            impl #lifetime_impl_generics IntoIterator for &'__sj #page #where_clause {
+                type Item = &'__sj #name;
+                type IntoIter = ::std::iter::Map<
+                    ::std::iter::FilterMap<
+                        ::std::slice::Iter<'__sj, #name_tag>,
+                        fn(&'__sj #name_tag) -> Option<&'__sj #name_and_metadata>,
+                    >,
+                    fn(&'__sj #name_and_metadata) -> &'__sj #name,
+                >;
+
+                fn into_iter(self) -> Self::IntoIter {
+                    self.#plural_snake
+                        .iter()
+                        .filter_map(#name_tag::try_get_inner as fn(_) -> _)
+                        .map(#name_and_metadata::item as fn(_) -> _)
+                }
+            }
+        }
+    };
+
+    // contextual parsing, applying a caller provided context to each item as soon as
+    // it is deserialized
+    let quoted_seed = if sj.seed() && sj.pagination().is_some() {
+        attribute_error(
+            sj,
+            "seed",
+            "the `seed` attribute cannot be combined with `pagination`",
+        )
+    } else if sj.seed() {
+        let seed = quote::format_ident!("{}Seed", sj.plural());
+        let key_matches = if sj.case_insensitive_key() {
+            quote! { key.eq_ignore_ascii_case(#plural_snake_s) #(|| key.eq_ignore_ascii_case(#key_aliases))* }
+        } else {
+            quote! { key == #plural_snake_s #(|| key == #key_aliases)* }
+        };
+
+        quote! {
+            /// Deserializes a collection applying a context to each of its items, ie. to
+            /// resolve relative links in metadata against a base URL.
+            #vis struct #seed<C, F> {
+                context: C,
+                apply: F,
+            }
+
+            impl<C, F> #seed<C, F>
+            where
+                F: Fn(&C, &mut #name_and_metadata),
+            {
+                pub fn new(context: C, apply: F) -> Self {
+                    #seed { context, apply }
+                }
+            }
+
+            impl<'de, C, F> ::serde::de::DeserializeSeed<'de> for #seed<C, F>
+            where
+                F: Fn(&C, &mut #name_and_metadata),
+            {
+                type Value = #plural;
+
+                fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+                where
+                    D: ::serde::Deserializer<'de>,
+                {
+                    use ::serde::de::{DeserializeSeed, Error, IgnoredAny, MapAccess, SeqAccess, Visitor};
+
+                    struct ItemsVisitor<'s, C, F>(&'s #seed<C, F>);
+
+                    impl<'de, 's, C, F> DeserializeSeed<'de> for ItemsVisitor<'s, C, F>
+                    where
+                        F: Fn(&C, &mut #name_and_metadata),
+                    {
+                        type Value = Vec<#name_tag>;
+
+                        fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+                        where
+                            D: ::serde::Deserializer<'de>,
+                        {
+                            deserializer.deserialize_seq(self)
+                        }
+                    }
+
+                    impl<'de, 's, C, F> Visitor<'de> for ItemsVisitor<'s, C, F>
+                    where
+                        F: Fn(&C, &mut #name_and_metadata),
+                    {
+                        type Value = Vec<#name_tag>;
+
+                        fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                            write!(f, "a sequence of wrapped items")
+                        }
+
+                        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+                        where
+                            A: SeqAccess<'de>,
+                        {
+                            let mut items = Vec::new();
+                            while let Some(mut tag) = seq.next_element::<#name_tag>()? {
+                                if let Some(inner) = tag.try_get_inner_mut() {
+                                    (self.0.apply)(&self.0.context, inner);
+                                }
+                                items.push(tag);
+                            }
+
+                            Ok(items)
+                        }
+                    }
+
+                    struct PluralVisitor<C, F>(#seed<C, F>);
+
+                    impl<'de, C, F> Visitor<'de> for PluralVisitor<C, F>
+                    where
+                        F: Fn(&C, &mut #name_and_metadata),
+                    {
+                        type Value = #plural;
+
+                        fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                            write!(f, "a map with a `{}` key", #plural_snake_s)
+                        }
+
+                        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+                        where
+                            A: MapAccess<'de>,
+                        {
+                            let mut items = None;
+                            while let Some(key) = map.next_key::<String>()? {
+                                if #key_matches {
+                                    if items.is_some() {
+                                        return Err(A::Error::duplicate_field(#plural_snake_s));
+                                    }
+                                    items = Some(map.next_value_seed(ItemsVisitor(&self.0))?);
+                                } else {
+                                    map.next_value::<IgnoredAny>()?;
+                                }
+                            }
+                            let items = items.ok_or_else(|| A::Error::missing_field(#plural_snake_s))?;
+                            #index_items
+
+                            Ok(#plural { #plural_snake: items })
+                        }
+                    }
+
+                    deserializer.deserialize_map(PluralVisitor(self))
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let quoted_trait_object = match sj.trait_object() {
+        Some(trait_object) => quote! {
+            impl #impl_generics #plural #where_clause {
+                pub fn into_boxed_items(self) -> Vec<Box<dyn #trait_object>> {
+                    self.into_items()
                         .into_iter()
-                        .map(|item| #name_tag::Tag(#name_and_metadata {
-                            item,
-                            metadata: None,
-                        })).collect::<Vec<_>>(),
+                        .map(|item| Box::new(item) as Box<dyn #trait_object>)
+                        .collect()
+                }
+            }
+        },
+        None => quote! {},
+    };
+
+    // marker types exposing the keys used by Porta for type-safe routing
+    let quoted_resource = match sj.resource() {
+        Some(resource) => {
+            let marker = quote::format_ident!("{}Resource", sj.name());
+
+            quote! {
+                #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+                #vis struct #marker;
+
+                impl #marker {
+                    pub const SINGULAR_KEY: &'static str = #name_snake_s;
+                    pub const PLURAL_KEY: &'static str = #plural_snake_s;
+                }
+
+                impl AsRef<str> for #marker {
+                    fn as_ref(&self) -> &str {
+                        Self::PLURAL_KEY
+                    }
+                }
+
+                impl #resource for #marker {
+                    type Item = #name;
+                    type Collection = #plural;
+
+                    const SINGULAR_KEY: &'static str = #name_snake_s;
+                    const PLURAL_KEY: &'static str = #plural_snake_s;
                 }
             }
         }
+        None => quote! {},
+    };
 
-        impl From<#plural> for Vec<#name_and_metadata> {
-            fn from(mrs: #plural) -> Self {
-                mrs.#plural_snake.into_iter()
-                    .map(#name_tag::into_inner)
-                    .collect()
+    // links the item to its generated types for code generic over resources
+    let quoted_straitjacketed = match sj.straitjacketed() {
+        Some(straitjacketed) => quote! {
+            impl #impl_generics #straitjacketed for #name #where_clause {
+                type Plural = #plural;
+                type Metadata = #metadata;
+
+                const SNAKE: &'static str = #name_snake_s;
+            }
+        },
+        None => quote! {},
+    };
+
+    // exports of the items as CSV rows
+    #[cfg(feature = "csv")]
+    let quoted_csv = quote! {
+        impl #impl_generics #plural #where_clause {
+            pub fn to_csv(&self) -> Result<String, ::csv::Error> {
+                let mut writer = ::csv::Writer::from_writer(Vec::new());
+                for inner in self.#plural_snake.iter().filter_map(#name_tag::try_get_inner) {
+                    writer.serialize(inner.item())?;
+                }
+                let bytes = writer
+                    .into_inner()
+                    .map_err(|e| ::csv::Error::from(e.into_error()))?;
+                String::from_utf8(bytes).map_err(|e| {
+                    ::csv::Error::from(::std::io::Error::new(::std::io::ErrorKind::InvalidData, e))
+                })
+            }
+        }
+    };
+    #[cfg(not(feature = "csv"))]
+    let quoted_csv = attribute_error(sj, "csv", "the `csv` attribute requires the `csv` feature");
+    let quoted_csv = if sj.csv() {
+        quoted_csv
+    } else {
+        quote! {}
+    };
+
+    // binary round-trips for caching, through a representation which keeps the metadata
+    // and avoids the flattening unsupported by non self-describing formats
+    #[cfg(feature = "bincode")]
+    let quoted_bincode = {
+        let (metadata_binding, metadata_assign) = if sj.skip_metadata() {
+            (quote! { _metadata }, quote! {})
+        } else {
+            (quote! { metadata }, quote! { metadata, })
+        };
+        let (extra_ty, extra_item, extra_pattern, extra_field) = if sj.moved_fields().is_empty() {
+            (quote! {}, quote! {}, quote! {}, quote! { #extra_init })
+        } else {
+            let extra = quote::format_ident!("{}ExtraMetadata", sj.name());
+            (
+                quote! { , Option<#extra> },
+                quote! { , &inner.extra_metadata },
+                quote! { , extra_metadata },
+                quote! { extra_metadata, },
+            )
+        };
+        let (page_ty, page_value, page_pattern, page_field) = match sj.pagination() {
+            Some(pagination) => (
+                quote! { , Option<#pagination> },
+                quote! { , &self.page },
+                quote! { , page },
+                quote! { page, },
+            ),
+            None => (quote! {}, quote! {}, quote! {}, quote! {}),
+        };
+
+        quote! {
+            impl #impl_generics #plural #where_clause {
+                pub fn to_bytes(&self) -> Result<Vec<u8>, ::bincode::Error> {
+                    let items = self
+                        .#plural_snake
+                        .iter()
+                        .filter_map(#name_tag::try_get_inner)
+                        .map(|inner| (&inner.#item_field, &#inner_metadata #extra_item))
+                        .collect::<Vec<_>>();
+                    ::bincode::serialize(&(items #page_value))
+                }
+
+                pub fn from_bytes(bytes: &[u8]) -> Result<#plural, ::bincode::Error> {
+                    let (items #page_pattern): (Vec<(#name, Option<#metadata> #extra_ty)> #page_ty) =
+                        ::bincode::deserialize(bytes)?;
+
+                    let items = items
+                        .into_iter()
+                        .map(|(item, #metadata_binding #extra_pattern)| {
+                            #name_tag::#tag_variant(#name_and_metadata {
+                                #item_field: item,
+                                #metadata_assign
+                                #extra_field
+                                #index_init
+                            })
+                        })
+                        .collect();
+                    #index_items
+
+                    Ok(#plural {
+                        #plural_snake: items,
+                        #page_field
+                    })
+                }
             }
         }
+    };
+    #[cfg(not(feature = "bincode"))]
+    let quoted_bincode = attribute_error(
+        sj,
+        "bincode",
+        "the `bincode` attribute requires the `bincode` feature",
+    );
+    let quoted_bincode = if sj.bincode() {
+        quoted_bincode
+    } else {
+        quote! {}
+    };
+
+    let quoted_strict = if sj.strict_items() {
+        let strict = quote::format_ident!("Strict{}", sj.name_and_metadata());
+        let strict_metadata = if sj.skip_metadata() {
+            quote! {}
+        } else {
+            quote! { metadata: strict.metadata, }
+        };
+
+        quote! {
+            #[derive(Debug, PartialEq, Clone, ::serde::Serialize, ::serde::Deserialize)]
+            #[serde(deny_unknown_fields)]
+            #vis struct #strict {
+                #[serde(rename = #name_snake_s)]
+                #item_defaults_serde
+                pub item: #name,
+                #[serde(default)]
+                pub metadata: Option<#metadata>,
+            }
 
-        impl From<#plural> for Vec<#name> {
-            fn from(mrs: #plural) -> Self {
-                mrs.#plural_snake.into_iter().map(|mr| {
-                    mr.into_inner().into_item()
-                }).collect()
+            impl #impl_generics From<#strict> for #name_and_metadata #where_clause {
+                fn from(strict: #strict) -> Self {
+                    #name_and_metadata {
+                        #item_field: strict.item,
+                        #strict_metadata
+                        #extra_init
+                        #index_init
+                    }
+                }
             }
         }
+    } else {
+        quote! {}
     };
 
-    // avoiding the Vec could be done via unstable std::array::IntoIter
-    let q = vec![c, quoted_plural.into()];
-    // emit the generated code
-    TokenStream::from_iter(q.into_iter())
+    // singular responses only need the wrapper and tag types
+    if sj.no_plural() {
+        return quoted_singular;
+    }
+
+    quote! {
+        #quoted_singular
+        #quoted_plural
+        #quoted_sort_keys
+        #quoted_strict
+        #quoted_serialize
+        #quoted_deserialize
+        #quoted_page_accessor
+        #quoted_json
+        #quoted_tokio
+        #quoted_reqwest
+        #quoted_lenient
+        #quoted_id
+        #quoted_position
+        #quoted_filters
+        #quoted_resource
+        #quoted_straitjacketed
+        #quoted_trait_object
+        #quoted_item_slice
+        #quoted_seed
+        #quoted_paginated
+        #quoted_proptest
+        #quoted_csv
+        #quoted_bincode
+    }
 }
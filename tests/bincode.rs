@@ -0,0 +1,48 @@
+#![cfg(feature = "bincode")]
+use serde::{Deserialize, Serialize};
+use straitjacket_macro::straitjacket;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct Metadata {
+    created_at: String,
+    updated_at: String,
+}
+
+#[straitjacket(bincode)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct MappingRule {
+    id: u64,
+    pattern: String,
+    http_method: String,
+}
+
+#[test]
+fn it_round_trips_through_bytes() {
+    let body = r##"{
+        "mapping_rules": [
+          {
+            "mapping_rule": {
+              "id": 1,
+              "pattern": "/",
+              "http_method": "GET",
+              "created_at": "2019-03-19T09:04:35Z",
+              "updated_at": "2019-03-19T09:04:39Z"
+            }
+          },
+          {
+            "mapping_rule": {
+              "id": 2,
+              "pattern": "/",
+              "http_method": "POST"
+            }
+          }
+        ]}"##;
+    let mapping_rules: MappingRules = serde_json::from_str(body).unwrap();
+    let bytes = mapping_rules.to_bytes().unwrap();
+    let cached = MappingRules::from_bytes(&bytes).unwrap();
+    assert_eq!(cached, mapping_rules);
+
+    let cached: Vec<MappingRuleAndMetadata> = cached.into();
+    assert_eq!(cached[0].metadata().unwrap().updated_at, "2019-03-19T09:04:39Z");
+    assert!(cached[1].metadata().is_none());
+}
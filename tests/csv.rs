@@ -0,0 +1,51 @@
+#![cfg(feature = "csv")]
+use serde::{Deserialize, Serialize};
+use straitjacket_macro::straitjacket;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct Metadata {
+    created_at: String,
+    updated_at: String,
+}
+
+#[straitjacket(csv)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct MappingRule {
+    id: u64,
+    pattern: String,
+    http_method: String,
+    delta: u64,
+}
+
+#[test]
+fn it_exports_items_as_csv() {
+    let body = r##"{
+        "mapping_rules": [
+          {
+            "mapping_rule": {
+              "id": 1,
+              "pattern": "/",
+              "http_method": "GET",
+              "delta": 1,
+              "created_at": "2019-03-19T09:04:35Z",
+              "updated_at": "2019-03-19T09:04:39Z"
+            }
+          },
+          {
+            "mapping_rule": {
+              "id": 2,
+              "pattern": "/",
+              "http_method": "POST",
+              "delta": 2,
+              "created_at": "2019-03-19T09:04:36Z",
+              "updated_at": "2019-03-19T09:04:39Z"
+            }
+          }
+        ]}"##;
+    let mapping_rules: MappingRules = serde_json::from_str(body).unwrap();
+    let csv = mapping_rules.to_csv().unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "id,pattern,http_method,delta");
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[2], "2,/,POST,2");
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "proptest")]
+use proptest::prelude::*;
+use serde::{Deserialize, Serialize};
+use straitjacket_macro::straitjacket;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct Metadata {
+    created_at: String,
+    updated_at: String,
+}
+
+#[straitjacket(proptest)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Limit {
+    id: u64,
+    period: String,
+    value: u64,
+}
+
+impl Arbitrary for Limit {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (any::<u64>(), "[a-z]{1,8}", any::<u64>())
+            .prop_map(|(id, period, value)| Limit { id, period, value })
+            .boxed()
+    }
+}
+
+#[straitjacket(proptest, no_from = "vec_to_plural")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Quota {
+    value: u64,
+}
+
+impl Arbitrary for Quota {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        any::<u64>().prop_map(|value| Quota { value }).boxed()
+    }
+}
+
+proptest! {
+    #[test]
+    fn it_round_trips_generated_collections(limits in Limits::strategy()) {
+        let json = serde_json::to_string(&limits).unwrap();
+        let parsed: Limits = serde_json::from_str(&json).unwrap();
+        prop_assert_eq!(parsed, limits);
+    }
+
+    #[test]
+    fn it_generates_collections_without_conversions(quotas in Quotas::strategy()) {
+        let json = serde_json::to_string(&quotas).unwrap();
+        let parsed: Quotas = serde_json::from_str(&json).unwrap();
+        prop_assert_eq!(parsed, quotas);
+    }
+}
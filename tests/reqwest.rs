@@ -0,0 +1,47 @@
+#![cfg(feature = "reqwest")]
+use serde::{Deserialize, Serialize};
+use straitjacket_macro::straitjacket;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct Metadata {
+    created_at: String,
+    updated_at: String,
+}
+
+#[straitjacket]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct MappingRule {
+    id: u64,
+    pattern: String,
+}
+
+fn response(status: u16, body: &'static str) -> reqwest::Response {
+    http::Response::builder()
+        .status(status)
+        .body(body)
+        .unwrap()
+        .into()
+}
+
+#[tokio::test]
+async fn it_parses_collections_out_of_responses() {
+    let body = r#"{"mapping_rules": [{"mapping_rule": {"id": 1, "pattern": "/"}}]}"#;
+    let mapping_rules = MappingRules::from_response(response(200, body)).await.unwrap();
+    assert_eq!(
+        mapping_rules.into_items(),
+        vec![MappingRule { id: 1, pattern: "/".into() }]
+    );
+}
+
+#[tokio::test]
+async fn it_tells_unsuccessful_statuses_apart_from_parsing_errors() {
+    let error = MappingRules::from_response(response(404, r#"{"status": "Not found"}"#))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, MappingRulesResponseError::Status(status) if status == 404));
+
+    let error = MappingRules::from_response(response(200, r#"{"mapping_rules": 1}"#))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, MappingRulesResponseError::Parse(_)));
+}
@@ -1,5 +1,10 @@
 // There is currently a requirement to test a macro crate outside its own crate.
-use straitjacket_macro::straitjacket;
+#![allow(
+    clippy::single_component_path_imports,
+    clippy::assertions_on_constants,
+    clippy::needless_borrow
+)]
+use straitjacket_macro::{straitjacket, straitjacket_all};
 use serde::{Serialize, Deserialize};
 use serde_json;
 
@@ -16,7 +21,13 @@ struct MyMetadata {
     links: Vec<Link>,
 }
 
-#[straitjacket(metadata = "MyMetadata")]
+#[straitjacket(
+    metadata = "MyMetadata",
+    id_field = "id",
+    position_field = "position",
+    filter_fields = "http_method",
+    seed
+)]
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Default)]
 pub struct MappingRule {
     id: u64,
@@ -28,6 +39,64 @@ pub struct MappingRule {
     last: bool,
 }
 
+const BODY: &str = r##"{
+    "mapping_rules": [
+      {
+        "mapping_rule": {
+          "id": 375841,
+          "metric_id": 2555418191879,
+          "pattern": "/",
+          "http_method": "GET",
+          "delta": 1,
+          "position": 1,
+          "last": false,
+          "created_at": "2019-03-19T09:04:35Z",
+          "updated_at": "2019-03-19T09:04:39Z",
+          "links": [
+            {
+              "rel": "self",
+              "href": "/admin/api/services/2555417777820/proxy/mapping_rules/375841"
+            },
+            {
+              "rel": "service",
+              "href": "/admin/api/services/2555417777820"
+            },
+            {
+              "rel": "proxy",
+              "href": "/admin/api/services/2555417777820/proxy"
+            }
+          ]
+        }
+      },
+      {
+        "mapping_rule": {
+          "id": 375842,
+          "metric_id": 2555418191880,
+          "pattern": "/",
+          "http_method": "POST",
+          "delta": 1,
+          "position": 2,
+          "last": false,
+          "created_at": "2019-03-19T09:04:36Z",
+          "updated_at": "2019-03-19T09:04:39Z",
+          "links": [
+            {
+              "rel": "self",
+              "href": "/admin/api/services/2555417777820/proxy/mapping_rules/375842"
+            },
+            {
+              "rel": "service",
+              "href": "/admin/api/services/2555417777820"
+            },
+            {
+              "rel": "proxy",
+              "href": "/admin/api/services/2555417777820/proxy"
+            }
+          ]
+        }
+      }
+    ]}"##;
+
 #[test]
 // This is just a compile test, as we should generate the below types
 fn t() {
@@ -126,3 +195,2088 @@ fn it_parses() {
     let mapping_rules = mapping_rules.unwrap();
     println!("PARSED:\n{}", serde_json::to_string_pretty(&mapping_rules).unwrap());
 }
+
+#[test]
+fn it_converts_empty_collections_to_none() {
+    let mapping_rules = MappingRules::from(vec![]);
+    assert!(mapping_rules.into_non_empty().is_none());
+}
+
+#[test]
+fn it_converts_non_empty_collections_to_some() {
+    let mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    let mapping_rules = mapping_rules.into_non_empty();
+    assert!(mapping_rules.is_some());
+    assert_eq!(mapping_rules.unwrap().get_inner().len(), 2);
+}
+
+#[test]
+fn it_parses_double_encoded_collections() {
+    let outer = serde_json::json!({ "payload": BODY }).to_string();
+    let mapping_rules = MappingRules::from_double_encoded(&outer, "payload");
+    assert!(mapping_rules.is_ok());
+    assert_eq!(mapping_rules.unwrap().get_inner().len(), 2);
+}
+
+#[test]
+fn it_fails_double_encoded_collections_without_a_string_field() {
+    let outer = serde_json::json!({ "payload": 42 }).to_string();
+    assert!(MappingRules::from_double_encoded(&outer, "payload").is_err());
+    assert!(MappingRules::from_double_encoded(&outer, "missing").is_err());
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BackendExtra {
+    description: String,
+}
+
+#[straitjacket(metadata = "MyMetadata")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Backend {
+    id: u64,
+    #[serde(flatten)]
+    extra: BackendExtra,
+}
+
+#[test]
+fn it_nests_items_which_use_flatten() {
+    let body = r##"{
+        "backends": [
+          {
+            "backend": {
+              "backend": {
+                "id": 42,
+                "description": "my backend"
+              },
+              "created_at": "2019-03-19T09:04:35Z",
+              "updated_at": "2019-03-19T09:04:39Z",
+              "links": []
+            }
+          }
+        ]}"##;
+    let backends: Backends = serde_json::from_str(body).unwrap();
+    let backends: Vec<BackendAndMetadata> = backends.into();
+    assert_eq!(backends[0].item().extra.description, "my backend");
+    assert!(backends[0].metadata().is_some());
+}
+
+#[straitjacket(metadata = "MyMetadata", lenient_items)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Metric {
+    id: u64,
+    system_name: String,
+}
+
+#[test]
+fn it_parses_leniently_reporting_malformed_items() {
+    let body = r##"{
+        "metrics": [
+          { "metric": { "id": 1, "system_name": "hits" } },
+          { "metric": { "id": "two", "system_name": "bytes" } },
+          { "metric": { "id": 3, "system_name": "requests" } }
+        ]}"##;
+    let parsed: ParsedMetrics = serde_json::from_str(body).unwrap();
+    assert_eq!(parsed.errors().len(), 1);
+    assert_eq!(parsed.errors()[0].0, 1);
+
+    let (metrics, _) = parsed.into_parts();
+    let metrics: Vec<Metric> = metrics.into();
+    assert_eq!(metrics.len(), 2);
+    assert_eq!(metrics[0].system_name, "hits");
+    assert_eq!(metrics[1].system_name, "requests");
+}
+
+#[test]
+fn it_exposes_the_collection_key() {
+    assert_eq!(MappingRules::collection_key(), "mapping_rules");
+}
+
+#[straitjacket(metadata = "MyMetadata", wrap_depth = "2")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Policy {
+    id: u64,
+    name: String,
+}
+
+#[test]
+fn it_parses_doubly_wrapped_items() {
+    let body = r##"{
+        "policies": [
+          { "policy": { "policy": { "id": 1, "name": "cors" } } },
+          { "policy": { "policy": { "id": 2, "name": "echo" } } }
+        ]}"##;
+    let policies: Policies = serde_json::from_str(body).unwrap();
+    let json = serde_json::to_value(&policies).unwrap();
+    assert_eq!(json["policies"][1]["policy"]["policy"]["name"], "echo");
+
+    let policies: Vec<Policy> = policies.into();
+    assert_eq!(policies.len(), 2);
+    assert_eq!(policies[0], Policy { id: 1, name: "cors".into() });
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Account {
+    id: u64,
+    org_name: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct User {
+    id: u64,
+    username: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct Invitation {
+    id: u64,
+}
+
+straitjacket_all! {
+    pub Account(metadata = "MyMetadata"),
+    pub User(metadata = "MyMetadata", plural_snake = "account_users"),
+    Invitation(metadata = "MyMetadata"),
+}
+
+#[test]
+fn it_generates_multiple_resources_at_once() {
+    let accounts: Accounts =
+        serde_json::from_str(r#"{"accounts": [{"account": {"id": 1, "org_name": "acme"}}]}"#)
+            .unwrap();
+    let accounts: Vec<Account> = accounts.into();
+    assert_eq!(accounts[0].org_name, "acme");
+
+    let users: Users =
+        serde_json::from_str(r#"{"account_users": [{"user": {"id": 2, "username": "bob"}}]}"#)
+            .unwrap();
+    let users: Vec<User> = users.into();
+    assert_eq!(users[0].username, "bob");
+}
+
+#[test]
+fn it_keeps_the_visibility_of_each_resource() {
+    let invitations: Invitations =
+        serde_json::from_str(r#"{"invitations": [{"invitation": {"id": 3}}]}"#).unwrap();
+    assert_eq!(invitations.into_items(), vec![Invitation { id: 3 }]);
+}
+
+#[test]
+fn it_collects_metadata_by_id() {
+    let mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    let metadata = mapping_rules.metadata_by_id();
+    assert_eq!(metadata.len(), 2);
+    assert_eq!(metadata[&375842].created_at, "2019-03-19T09:04:36Z");
+    assert!(MappingRules::from(vec![MappingRule::default()])
+        .metadata_by_id()
+        .is_empty());
+}
+
+#[straitjacket(metadata = "MyMetadata", serialize_unwrapped)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Service {
+    id: u64,
+    name: String,
+}
+
+#[test]
+fn it_serializes_constructed_items_unwrapped() {
+    let services = Services::from(vec![Service { id: 1, name: "api".into() }]);
+    let json = serde_json::to_value(&services).unwrap();
+    assert_eq!(json, serde_json::json!({ "services": [{ "id": 1, "name": "api" }] }));
+}
+
+#[test]
+fn it_serializes_parsed_items_wrapped() {
+    let body = r##"{
+        "services": [
+          {
+            "service": {
+              "id": 1,
+              "name": "api",
+              "created_at": "2019-03-19T09:04:35Z",
+              "updated_at": "2019-03-19T09:04:39Z",
+              "links": []
+            }
+          }
+        ]}"##;
+    let services: Services = serde_json::from_str(body).unwrap();
+    let json = serde_json::to_value(&services).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({ "services": [{ "service": { "id": 1, "name": "api" } }] })
+    );
+}
+
+#[test]
+fn it_takes_items_until_a_position() {
+    let mapping_rules = MappingRules::from(
+        (1..=4)
+            .map(|position| MappingRule { id: position, position, ..Default::default() })
+            .collect::<Vec<_>>(),
+    );
+    let taken = mapping_rules.take_until_position(2);
+    assert_eq!(taken.iter().map(|mr| mr.id).collect::<Vec<_>>(), vec![1, 2]);
+    assert!(mapping_rules.take_until_position(0).is_empty());
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PageInfo {
+    current_page: u64,
+    total_pages: u64,
+}
+
+#[straitjacket(metadata = "MyMetadata", pagination = "PageInfo")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Application {
+    id: u64,
+    name: String,
+}
+
+#[test]
+fn it_parses_flattened_pagination() {
+    let body = r##"{
+        "applications": [
+          { "application": { "id": 1, "name": "mobile" } }
+        ],
+        "current_page": 2,
+        "total_pages": 3
+        }"##;
+    let applications: Applications = serde_json::from_str(body).unwrap();
+    assert_eq!(
+        applications.page(),
+        Some(&PageInfo { current_page: 2, total_pages: 3 })
+    );
+
+    let applications = Applications::from(vec![Application { id: 1, name: "mobile".into() }]);
+    assert!(applications.page().is_none());
+}
+
+#[test]
+fn it_collects_borrowed_items() {
+    let other: MappingRules = serde_json::from_str(BODY).unwrap();
+    let mapping_rules: MappingRules = other
+        .get_inner()
+        .iter()
+        .map(|tag| tag.get_inner().item())
+        .filter(|mr| mr.http_method == "POST")
+        .collect();
+    let mapping_rules: Vec<MappingRule> = mapping_rules.into();
+    assert_eq!(mapping_rules.len(), 1);
+    assert_eq!(mapping_rules[0].id, 375842);
+}
+
+// generated generic parameters must not clash with single letter item names
+#[straitjacket(metadata = "MyMetadata", no_plural_key, track_index)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct I {
+    id: u64,
+}
+
+#[test]
+fn it_collects_borrowed_items_named_like_type_parameters() {
+    let items = vec![I { id: 1 }, I { id: 2 }];
+    let collected: Is = items.iter().collect();
+    assert_eq!(collected.into_items(), items);
+}
+
+#[test]
+fn it_collects_owned_items_named_like_type_parameters() {
+    let collected: Is = vec![I { id: 1 }, I { id: 2 }].into_iter().collect();
+    assert_eq!(collected.into_items(), vec![I { id: 1 }, I { id: 2 }]);
+}
+
+#[test]
+fn it_strips_metadata() {
+    let mut mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    mapping_rules.strip_metadata();
+    assert!(mapping_rules
+        .get_inner()
+        .iter()
+        .all(|tag| tag.get_inner().metadata().is_none()));
+
+    let json = serde_json::to_string(&mapping_rules).unwrap();
+    assert!(!json.contains("created_at"));
+    assert!(!json.contains("links"));
+}
+
+#[test]
+fn it_binary_searches_by_id() {
+    let mapping_rules = MappingRules::from(
+        [2, 4, 6]
+            .iter()
+            .map(|&id| MappingRule { id, ..Default::default() })
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(mapping_rules.binary_search_by_id(&4), Ok(1));
+    assert_eq!(mapping_rules.binary_search_by_id(&5), Err(2));
+}
+
+#[straitjacket(metadata = "MyMetadata", case_insensitive_key)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ApplicationPlan {
+    id: u64,
+    name: String,
+}
+
+#[test]
+fn it_matches_the_collection_key_ignoring_case() {
+    let body = r##"{
+        "Application_Plans": [
+          { "application_plan": { "id": 1, "name": "basic" } }
+        ]}"##;
+    let plans: ApplicationPlans = serde_json::from_str(body).unwrap();
+    let plans: Vec<ApplicationPlan> = plans.into();
+    assert_eq!(plans, vec![ApplicationPlan { id: 1, name: "basic".into() }]);
+
+    assert!(serde_json::from_str::<ApplicationPlans>(r#"{"plans": []}"#).is_err());
+}
+
+#[test]
+fn it_parses_bare_items() {
+    let mapping_rules: Vec<MappingRule> = MappingRules::parse_items(BODY).unwrap();
+    assert_eq!(mapping_rules.len(), 2);
+    assert_eq!(mapping_rules[1].http_method, "POST");
+    assert!(MappingRules::parse_items("{").is_err());
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct Timestamps {
+    created_at: String,
+    updated_at: String,
+}
+
+#[straitjacket(metadata = "Timestamps")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Feature {
+    id: u64,
+    name: String,
+    #[straitjacket_field(flatten_into_metadata)]
+    links: Vec<Link>,
+}
+
+#[test]
+fn it_moves_fields_into_metadata() {
+    let body = r##"{
+        "features": [
+          {
+            "feature": {
+              "id": 1,
+              "name": "ssl",
+              "created_at": "2019-03-19T09:04:35Z",
+              "updated_at": "2019-03-19T09:04:39Z",
+              "links": [{ "rel": "self", "href": "/admin/api/features/1" }]
+            }
+          }
+        ]}"##;
+    let features: Features = serde_json::from_str(body).unwrap();
+    let features: Vec<FeatureAndMetadata> = features.into();
+    assert_eq!(features[0].item(), &Feature { id: 1, name: "ssl".into() });
+    assert_eq!(
+        features[0].extra_metadata().unwrap().links[0].href,
+        "/admin/api/features/1"
+    );
+    assert_eq!(
+        features[0].metadata().unwrap().created_at,
+        "2019-03-19T09:04:35Z"
+    );
+}
+
+#[test]
+fn it_takes_the_collection_contents() {
+    let mut mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    let taken = mapping_rules.take();
+    assert_eq!(taken.get_inner().len(), 2);
+    assert!(mapping_rules.get_inner().is_empty());
+    assert_eq!(mapping_rules, MappingRules::default());
+}
+
+#[straitjacket(metadata = "MyMetadata", item_default)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Default)]
+pub struct Proxy {
+    endpoint: String,
+    sandbox_endpoint: String,
+    api_test_path: String,
+}
+
+#[test]
+fn it_parses_sparse_items_with_defaults() {
+    let body = r##"{
+        "proxies": [
+          { "proxy": { "endpoint": "https://api.example.com" } }
+        ]}"##;
+    let proxies: Proxies = serde_json::from_str(body).unwrap();
+    let proxies: Vec<Proxy> = proxies.into();
+    assert_eq!(
+        proxies[0],
+        Proxy { endpoint: "https://api.example.com".into(), ..Default::default() }
+    );
+}
+
+#[test]
+fn it_keeps_the_item_deserialization_strict_with_defaults() {
+    let sparse = r##"{ "endpoint": "https://api.example.com" }"##;
+    assert!(serde_json::from_str::<Proxy>(sparse).is_err());
+}
+
+#[test]
+fn it_chunks_items_into_batches() {
+    let mapping_rules = MappingRules::from(
+        (1..=5)
+            .map(|id| MappingRule { id, ..Default::default() })
+            .collect::<Vec<_>>(),
+    );
+    let batches = mapping_rules.chunks(2).collect::<Vec<_>>();
+    assert_eq!(batches.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 2, 1]);
+    assert_eq!(batches[2][0].id, 5);
+}
+
+pub trait Resource {
+    type Item;
+    type Collection;
+
+    const SINGULAR_KEY: &'static str;
+    const PLURAL_KEY: &'static str;
+}
+
+#[straitjacket(metadata = "MyMetadata", resource = "Resource")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ActiveDoc {
+    id: u64,
+    name: String,
+}
+
+fn plural_key<R: Resource>() -> &'static str {
+    R::PLURAL_KEY
+}
+
+#[test]
+fn it_exposes_resource_keys_through_a_trait() {
+    assert_eq!(plural_key::<ActiveDocResource>(), "active_docs");
+    assert_eq!(ActiveDocResource::SINGULAR_KEY, "active_doc");
+    assert_eq!(ActiveDocResource.as_ref(), "active_docs");
+}
+
+#[straitjacket(metadata = "Timestamps", serialize_null_metadata)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    id: u64,
+    url: String,
+}
+
+#[test]
+fn it_serializes_missing_metadata_as_null() {
+    let webhooks = Webhooks::from(vec![Webhook { id: 1, url: "https://example.com".into() }]);
+    let json = serde_json::to_value(&webhooks).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({ "webhooks": [{ "webhook": {
+            "id": 1,
+            "url": "https://example.com",
+            "created_at": null,
+            "updated_at": null,
+        }}]})
+    );
+}
+
+#[test]
+fn it_serializes_present_metadata_with_null_metadata() {
+    let body = r##"{
+        "webhooks": [
+          {
+            "webhook": {
+              "id": 1,
+              "url": "https://example.com",
+              "created_at": "2019-03-19T09:04:35Z",
+              "updated_at": "2019-03-19T09:04:39Z"
+            }
+          }
+        ]}"##;
+    let webhooks: Webhooks = serde_json::from_str(body).unwrap();
+    let json = serde_json::to_value(&webhooks).unwrap();
+    assert_eq!(json["webhooks"][0]["webhook"]["created_at"], "2019-03-19T09:04:35Z");
+}
+
+#[test]
+fn it_reserves_and_shrinks_capacity() {
+    let mut mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    let before = mapping_rules.clone();
+    mapping_rules.reserve(100);
+    assert!(mapping_rules.get_inner().capacity() >= 102);
+    mapping_rules.shrink_to_fit();
+    assert_eq!(mapping_rules, before);
+}
+
+#[test]
+fn it_parses_ndjson() {
+    let input = r#"{"mapping_rule": {"id": 1, "metric_id": 1, "pattern": "/", "http_method": "GET", "delta": 1, "position": 1, "last": false}}
+{"mapping_rule": {"id": 2, "metric_id": 1, "pattern": "/", "http_method": "POST", "delta": 1, "position": 2, "last": false}}
+{"mapping_rule": {"id": 3, "metric_id": 1, "pattern": "/", "http_method": "PUT", "delta": 1, "position": 3, "last": true}}
+"#;
+    let mapping_rules: Vec<MappingRule> = MappingRules::from_ndjson(input).unwrap().into();
+    assert_eq!(mapping_rules.len(), 3);
+    assert_eq!(mapping_rules[2].http_method, "PUT");
+    assert!(MappingRules::from_ndjson("{\"mapping_rule\": 1}").is_err());
+}
+
+#[test]
+fn it_validates_serialization() {
+    let mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    assert!(mapping_rules.validate_serialization().is_ok());
+}
+
+#[straitjacket(metadata = "MyMetadata", other_variant)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Method {
+    id: u64,
+    system_name: String,
+}
+
+#[test]
+fn it_parses_unknown_item_kinds_as_placeholders() {
+    let body = r##"{
+        "methods": [
+          { "method": { "id": 1, "system_name": "list" } },
+          { "metric": { "id": 2, "system_name": "hits" } },
+          { "method": { "id": 3, "system_name": "show" } }
+        ]}"##;
+    let methods: Methods = serde_json::from_str(body).unwrap();
+    assert_eq!(methods.get_inner()[1], MethodTag::Unknown);
+    assert!(methods.get_inner()[1].get_inner().is_none());
+
+    let methods: Vec<Method> = methods.into();
+    assert_eq!(
+        methods.iter().map(|m| m.id).collect::<Vec<_>>(),
+        vec![1, 3]
+    );
+}
+
+#[test]
+fn it_chunks_known_items_into_full_batches() {
+    let body = r##"{
+        "methods": [
+          { "method": { "id": 1, "system_name": "list" } },
+          { "metric": { "id": 2, "system_name": "hits" } },
+          { "method": { "id": 3, "system_name": "show" } },
+          { "method": { "id": 4, "system_name": "edit" } }
+        ]}"##;
+    let methods: Methods = serde_json::from_str(body).unwrap();
+    let batches = methods.chunks(2).collect::<Vec<_>>();
+    assert_eq!(batches.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 1]);
+    assert_eq!(batches[0].iter().map(|m| m.id).collect::<Vec<_>>(), vec![1, 3]);
+}
+
+#[test]
+fn it_skips_unknown_placeholders_when_serializing() {
+    let body = r##"{
+        "methods": [
+          { "method": { "id": 1, "system_name": "list" } },
+          { "metric": { "id": 2, "system_name": "hits" } }
+        ]}"##;
+    let methods: Methods = serde_json::from_str(body).unwrap();
+    assert!(methods.validate_serialization().is_ok());
+
+    let json = serde_json::to_string(&methods).unwrap();
+    let reparsed: Methods = serde_json::from_str(&json).unwrap();
+    assert_eq!(reparsed.get_inner().len(), 1);
+    assert_eq!(Vec::<Method>::from(reparsed), Vec::<Method>::from(methods));
+}
+
+#[test]
+fn it_serializes_into_a_writer() {
+    let mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    let mut buf: Vec<u8> = Vec::new();
+    mapping_rules.to_writer(&mut buf).unwrap();
+
+    let parsed: Vec<MappingRule> = serde_json::from_slice::<MappingRules>(&buf).unwrap().into();
+    assert_eq!(parsed, Vec::<MappingRule>::from(mapping_rules));
+}
+
+#[straitjacket(metadata = "Timestamps", metadata_when = "detailed")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Invoice {
+    id: u64,
+    state: String,
+}
+
+#[test]
+fn it_parses_metadata_only_when_detailed() {
+    let body = r##"{
+        "invoices": [
+          {
+            "invoice": {
+              "id": 1,
+              "state": "paid",
+              "detailed": true,
+              "created_at": "2019-03-19T09:04:35Z",
+              "updated_at": "2019-03-19T09:04:39Z"
+            }
+          },
+          {
+            "invoice": {
+              "id": 2,
+              "state": "open",
+              "created_at": 1552986275
+            }
+          }
+        ]}"##;
+    let invoices: Invoices = serde_json::from_str(body).unwrap();
+    let invoices: Vec<InvoiceAndMetadata> = invoices.into();
+    assert_eq!(
+        invoices[0].metadata().unwrap().created_at,
+        "2019-03-19T09:04:35Z"
+    );
+    assert_eq!(invoices[1].item().state, "open");
+    assert!(invoices[1].metadata().is_none());
+}
+
+#[test]
+fn it_borrows_items_with_their_metadata() {
+    let mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    let borrowed: Vec<(&MappingRule, Option<&MyMetadata>)> = (&mapping_rules).into();
+    assert_eq!(borrowed.len(), 2);
+    assert_eq!(borrowed[1].0.id, 375842);
+    assert_eq!(borrowed[1].1.unwrap().links.len(), 3);
+}
+
+#[straitjacket(metadata = "Timestamps")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename = "usage_limit")]
+pub struct Limit {
+    id: u64,
+    value: u64,
+}
+
+#[test]
+fn it_uses_the_container_rename_as_item_key() {
+    let body = r##"{
+        "limits": [
+          {
+            "usage_limit": {
+              "id": 3,
+              "value": 100,
+              "created_at": "2019-03-19T09:04:35Z",
+              "updated_at": "2019-03-19T09:04:39Z"
+            }
+          }
+        ]}"##;
+    let limits: Limits = serde_json::from_str(body).unwrap();
+    let limits: Vec<Limit> = limits.into();
+    assert_eq!(limits, vec![Limit { id: 3, value: 100 }]);
+}
+
+#[straitjacket(metadata = "Timestamps")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename = "backend-usage")]
+pub struct BackendUsage {
+    id: u64,
+}
+
+#[test]
+fn it_uses_a_non_identifier_container_rename_as_item_key() {
+    let body = r##"{"backend_usages": [{"backend-usage": {"id": 5}}]}"##;
+    let usages: BackendUsages = serde_json::from_str(body).unwrap();
+    assert_eq!(
+        serde_json::to_string(&usages).unwrap(),
+        r#"{"backend_usages":[{"backend-usage":{"id":5}}]}"#
+    );
+    let usages: Vec<BackendUsage> = usages.into();
+    assert_eq!(usages, vec![BackendUsage { id: 5 }]);
+}
+
+#[straitjacket(metadata = "Timestamps", strict_items)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Gateway {
+    id: u64,
+    name: String,
+}
+
+#[test]
+fn it_rejects_unknown_fields_with_strict_items() {
+    let body = r##"{
+        "gateway": { "id": 7, "name": "apicast" },
+        "metadata": {
+          "created_at": "2019-03-19T09:04:35Z",
+          "updated_at": "2019-03-19T09:04:39Z"
+        }
+      }"##;
+    let strict: StrictGatewayAndMetadata = serde_json::from_str(body).unwrap();
+    let gateway: GatewayAndMetadata = strict.into();
+    assert_eq!(gateway.item().name, "apicast");
+    assert!(gateway.metadata().is_some());
+
+    let body = r##"{
+        "gateway": { "id": 7, "name": "apicast" },
+        "scope": "service"
+      }"##;
+    let err = serde_json::from_str::<StrictGatewayAndMetadata>(body).unwrap_err();
+    assert!(err.to_string().contains("unknown field `scope`"));
+}
+
+#[test]
+fn it_converts_into_a_json_value() {
+    use std::convert::TryFrom;
+
+    let mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    let value = serde_json::Value::try_from(mapping_rules).unwrap();
+    let items = value["mapping_rules"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[1]["mapping_rule"]["id"], 375842);
+}
+
+#[test]
+fn it_builds_empty_collections_in_const_context() {
+    const EMPTY: MappingRules = MappingRules::new_empty();
+    let empty: Vec<MappingRule> = EMPTY.into();
+    assert!(empty.is_empty());
+    assert_eq!(Applications::new_empty().page(), None);
+}
+
+#[straitjacket(metadata = "Timestamps", redact = "token, secret")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AccessToken {
+    id: u64,
+    name: String,
+    token: String,
+    secret: String,
+}
+
+#[test]
+fn it_redacts_sensitive_fields_in_debug_output() {
+    let body = r##"{
+        "access_tokens": [
+          {
+            "access_token": {
+              "id": 1,
+              "name": "ci",
+              "token": "s3cr3t-t0k3n",
+              "secret": "hunter2"
+            }
+          }
+        ]}"##;
+    let tokens: AccessTokens = serde_json::from_str(body).unwrap();
+    let tokens: Vec<AccessTokenAndMetadata> = tokens.into();
+    let debug = format!("{:?}", tokens[0]);
+    assert!(debug.contains("name: \"ci\""));
+    assert!(debug.contains("token: ***"));
+    assert!(!debug.contains("s3cr3t-t0k3n"));
+    assert!(!debug.contains("hunter2"));
+}
+
+#[test]
+fn it_pads_collections_to_a_given_length() {
+    let mut mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    let mut next_id = 0;
+    let mut filler = || {
+        next_id += 1;
+        MappingRule { id: next_id, ..Default::default() }
+    };
+    mapping_rules.pad_to(4, &mut filler);
+    mapping_rules.pad_to(3, &mut filler);
+    let mapping_rules: Vec<MappingRuleAndMetadata> = mapping_rules.into();
+    assert_eq!(mapping_rules.len(), 4);
+    assert_eq!(mapping_rules[3].item().id, 2);
+    assert!(mapping_rules[3].metadata().is_none());
+}
+
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+#[straitjacket(metadata = "Timestamps", trait_object = "Describe")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Plugin {
+    name: String,
+}
+
+impl Describe for Plugin {
+    fn describe(&self) -> String {
+        format!("plugin {}", self.name)
+    }
+}
+
+#[test]
+fn it_collects_items_as_trait_objects() {
+    let plugins = Plugins::from(vec![
+        Plugin { name: "auth".into() },
+        Plugin { name: "cors".into() },
+    ]);
+    let boxed: Vec<Box<dyn Describe>> = plugins.into_boxed_items();
+    let descriptions = boxed.iter().map(|p| p.describe()).collect::<Vec<_>>();
+    assert_eq!(descriptions, vec!["plugin auth", "plugin cors"]);
+}
+
+#[test]
+fn it_verifies_ids_are_unique() {
+    let mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    assert_eq!(mapping_rules.verify_unique_ids(), Ok(()));
+
+    let mut items: Vec<MappingRule> = mapping_rules.into();
+    items.push(items[0].clone());
+    items.push(items[0].clone());
+    let mapping_rules = MappingRules::from(items);
+    assert_eq!(mapping_rules.verify_unique_ids(), Err(vec![375841]));
+}
+
+#[straitjacket(metadata = "Timestamps", metadata_rename_all = "camelCase")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Domain {
+    id: u64,
+    host: String,
+}
+
+#[test]
+fn it_renames_metadata_keys() {
+    let body = r##"{
+        "domains": [
+          {
+            "domain": {
+              "id": 1,
+              "host": "api.example.com",
+              "createdAt": "2019-03-19T09:04:35Z",
+              "updatedAt": "2019-03-19T09:04:39Z"
+            }
+          },
+          {
+            "domain": {
+              "id": 2,
+              "host": "admin.example.com"
+            }
+          }
+        ]}"##;
+    let domains: Domains = serde_json::from_str(body).unwrap();
+    let domains: Vec<DomainAndMetadata> = domains.into();
+    assert_eq!(domains[0].item().host, "api.example.com");
+    assert_eq!(domains[0].metadata().unwrap().updated_at, "2019-03-19T09:04:39Z");
+    assert!(domains[1].metadata().is_none());
+}
+
+#[test]
+fn it_splits_collections_into_parts() {
+    let mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    let expected: Vec<MappingRuleAndMetadata> = mapping_rules.clone().into();
+    let (items, metadata) = mapping_rules.into_parts();
+    assert_eq!(items.len(), metadata.len());
+    for (i, inner) in expected.iter().enumerate() {
+        assert_eq!(&items[i], inner.item());
+        assert_eq!(metadata[i].as_ref(), inner.metadata());
+    }
+}
+
+#[test]
+fn it_deserializes_with_a_context_carrying_seed() {
+    use serde::de::DeserializeSeed;
+
+    let seed = MappingRulesSeed::new(
+        "https://admin.example.com",
+        |base: &&str, inner: &mut MappingRuleAndMetadata| {
+            if let Some(metadata) = inner.metadata_mut() {
+                for link in metadata.links.iter_mut() {
+                    link.href = format!("{}{}", base, link.href);
+                }
+            }
+        },
+    );
+    let mut deserializer = serde_json::Deserializer::from_str(BODY);
+    let mapping_rules = seed.deserialize(&mut deserializer).unwrap();
+    let mapping_rules: Vec<MappingRuleAndMetadata> = mapping_rules.into();
+    assert_eq!(
+        mapping_rules[0].metadata().unwrap().links[1].href,
+        "https://admin.example.com/admin/api/services/2555417777820"
+    );
+}
+
+#[test]
+fn it_compares_wrappers_with_bare_items() {
+    let mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    let mapping_rules: Vec<MappingRuleAndMetadata> = mapping_rules.into();
+    let expected = MappingRule {
+        id: 375841,
+        metric_id: 2555418191879,
+        pattern: "/".into(),
+        http_method: "GET".into(),
+        delta: 1,
+        position: 1,
+        last: false,
+    };
+    assert_eq!(mapping_rules[0], expected);
+    assert_ne!(mapping_rules[1], expected);
+}
+
+#[test]
+fn it_filters_items_by_string_fields() {
+    let mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    let posts = mapping_rules.with_http_method("POST");
+    assert_eq!(posts.len(), 1);
+    assert_eq!(posts[0].id, 375842);
+    assert!(mapping_rules.with_http_method("DELETE").is_empty());
+}
+
+#[straitjacket(metadata = "Timestamps", plural = "StaffMembers")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Staff {
+    id: u64,
+}
+
+#[test]
+fn it_derives_the_snake_case_plural_from_the_plural() {
+    assert_eq!(StaffMembers::collection_key(), "staff_members");
+    let body = r#"{"staff_members": [{"staff": {"id": 1}}]}"#;
+    let staff: Vec<Staff> = serde_json::from_str::<StaffMembers>(body).unwrap().into();
+    assert_eq!(staff, vec![Staff { id: 1 }]);
+}
+
+#[test]
+fn it_builds_collections_from_value_arrays() {
+    let envelope: serde_json::Value = serde_json::from_str(BODY).unwrap();
+    let items = envelope["mapping_rules"].as_array().unwrap().clone();
+    let mapping_rules = MappingRules::from_value_array(items).unwrap();
+    assert_eq!(mapping_rules, serde_json::from_str::<MappingRules>(BODY).unwrap());
+
+    let invalid = vec![serde_json::json!({ "mapping_rule": { "id": "nope" } })];
+    assert!(MappingRules::from_value_array(invalid).is_err());
+}
+
+mod without_serde_imports {
+    use straitjacket_macro::straitjacket;
+
+    #[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
+    pub struct Metadata {
+        created_at: String,
+    }
+
+    #[straitjacket]
+    #[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
+    pub struct Limit {
+        pub id: u64,
+    }
+}
+
+#[test]
+fn it_does_not_require_serde_imports() {
+    use without_serde_imports::{Limit, Limits};
+
+    let body = r#"{"limits":[{"limit":{"id":1,"created_at":"2019-03-19T09:04:35Z"}}]}"#;
+    let limits: Limits = serde_json::from_str(body).unwrap();
+    assert_eq!(serde_json::to_string(&limits).unwrap(), r#"{"limits":[{"limit":{"id":1}}]}"#);
+    let limits: Vec<Limit> = limits.into();
+    assert_eq!(limits, vec![Limit { id: 1 }]);
+}
+
+#[straitjacket(metadata = "Timestamps", item_field = "inner")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct LineItem {
+    id: u64,
+    item: String,
+}
+
+#[test]
+fn it_customizes_the_wrapper_item_field() {
+    let body = r##"{
+        "line_items": [
+          {
+            "line_item": {
+              "id": 1,
+              "item": "fixed fee",
+              "created_at": "2019-03-19T09:04:35Z",
+              "updated_at": "2019-03-19T09:04:39Z"
+            }
+          }
+        ]}"##;
+    let line_items: LineItems = serde_json::from_str(body).unwrap();
+    let line_items: Vec<LineItemAndMetadata> = line_items.into();
+    assert_eq!(line_items[0].item().item, "fixed fee");
+    assert!(line_items[0].metadata().is_some());
+    assert_eq!(line_items[0].clone().into_item(), LineItem { id: 1, item: "fixed fee".into() });
+}
+
+#[straitjacket(metadata = "Timestamps", sort_keys)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Setting {
+    name: String,
+    values: std::collections::HashMap<String, u64>,
+}
+
+#[test]
+fn it_serializes_with_sorted_keys() {
+    let body = r##"{
+        "settings": [
+          { "setting": { "name": "limits", "values": { "c": 3, "a": 1, "d": 4, "b": 2 } } }
+        ]}"##;
+    let first: Settings = serde_json::from_str(body).unwrap();
+    let second: Settings = serde_json::from_str(body).unwrap();
+    let serialized = serde_json::to_string(&first).unwrap();
+    assert_eq!(serialized, serde_json::to_string(&second).unwrap());
+    assert_eq!(
+        serialized,
+        r#"{"settings":[{"setting":{"name":"limits","values":{"a":1,"b":2,"c":3,"d":4}}}]}"#
+    );
+}
+
+#[test]
+fn it_converts_into_fixed_size_arrays() {
+    use std::convert::TryFrom;
+
+    let mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    let [first, second] = <[MappingRule; 2]>::try_from(mapping_rules.clone()).unwrap();
+    assert_eq!((first.id, second.id), (375841, 375842));
+
+    let mismatch = <[MappingRule; 3]>::try_from(mapping_rules).unwrap_err();
+    assert_eq!(mismatch.len(), 2);
+}
+
+#[straitjacket(metadata = "Timestamps", strict_items = true, other_variant = false)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Region {
+    id: u64,
+}
+
+#[test]
+fn it_accepts_boolean_flag_values() {
+    let strict: StrictRegionAndMetadata = serde_json::from_str(r#"{"region": {"id": 1}}"#).unwrap();
+    let region: RegionAndMetadata = strict.into();
+    // without `other_variant` the inner value is always available
+    let tag = RegionTag::Tag(region.clone());
+    assert_eq!(tag.get_inner(), &region);
+}
+
+mod without_metadata {
+    use serde::{Deserialize, Serialize};
+    use straitjacket_macro::straitjacket;
+
+    #[straitjacket(skip_metadata = true, strict_items, redact = "secret")]
+    #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+    pub struct Key {
+        pub id: u64,
+        pub secret: String,
+    }
+}
+
+#[test]
+fn it_skips_metadata() {
+    use without_metadata::{Key, KeyAndMetadata, Keys};
+
+    let body = r#"{"keys":[{"key":{"id":1,"secret":"hunter2","created_at":"2019-03-19T09:04:35Z"}}]}"#;
+    let mut keys: Keys = serde_json::from_str(body).unwrap();
+    keys.strip_metadata();
+    assert_eq!(serde_json::to_string(&keys).unwrap(), r#"{"keys":[{"key":{"id":1,"secret":"hunter2"}}]}"#);
+    let (items, metadata) = keys.clone().into_parts();
+    assert_eq!(metadata, vec![None]);
+    let keys: Vec<KeyAndMetadata> = keys.into();
+    assert!(keys[0].metadata().is_none());
+    assert_eq!(keys[0].clone().into_item(), items[0]);
+    assert_eq!(items, vec![Key { id: 1, secret: "hunter2".into() }]);
+}
+
+#[straitjacket(metadata = "MyMetadata", track_index, lenient_items, bincode)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    id: u64,
+    pattern: String,
+}
+
+#[test]
+fn it_tracks_the_source_index_of_items() {
+    let body = BODY.replace("mapping_rule", "rule");
+    let rules: Rules = serde_json::from_str(&body).unwrap();
+    let rules: Vec<RuleAndMetadata> = rules.into();
+    assert_eq!(rules.len(), 2);
+    for (i, rule) in rules.iter().enumerate() {
+        assert_eq!(rule.source_index(), i);
+    }
+}
+
+fn source_indices(rules: Rules) -> Vec<usize> {
+    Vec::<RuleAndMetadata>::from(rules)
+        .iter()
+        .map(RuleAndMetadata::source_index)
+        .collect()
+}
+
+#[test]
+fn it_tracks_the_source_index_of_leniently_parsed_items() {
+    let body = r##"{
+        "rules": [
+          { "rule": { "id": 1, "pattern": "/a" } },
+          { "rule": { "id": "two", "pattern": "/b" } },
+          { "rule": { "id": 3, "pattern": "/c" } }
+        ]}"##;
+    let parsed: ParsedRules = serde_json::from_str(body).unwrap();
+    let (rules, errors) = parsed.into_parts();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(source_indices(rules), vec![0, 2]);
+}
+
+#[test]
+fn it_tracks_the_source_index_of_ndjson_items() {
+    let input = r#"{ "rule": { "id": 1, "pattern": "/a" } }
+{ "rule": { "id": 2, "pattern": "/b" } }
+"#;
+    let rules = Rules::from_ndjson(input).unwrap();
+    assert_eq!(source_indices(rules), vec![0, 1]);
+}
+
+#[test]
+fn it_tracks_the_source_index_of_value_array_items() {
+    let values = vec![
+        serde_json::json!({ "rule": { "id": 1, "pattern": "/a" } }),
+        serde_json::json!({ "rule": { "id": 2, "pattern": "/b" } }),
+    ];
+    let rules = Rules::from_value_array(values).unwrap();
+    assert_eq!(source_indices(rules), vec![0, 1]);
+}
+
+#[test]
+fn it_tracks_the_source_index_of_bincode_items() {
+    let body = BODY.replace("mapping_rule", "rule");
+    let rules: Rules = serde_json::from_str(&body).unwrap();
+    let rules = Rules::from_bytes(&rules.to_bytes().unwrap()).unwrap();
+    assert_eq!(source_indices(rules), vec![0, 1]);
+}
+
+#[test]
+fn it_iterates_over_items() {
+    let mut mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    assert_eq!(mapping_rules.iter().map(|mr| mr.delta).sum::<u64>(), 2);
+    for mr in mapping_rules.iter_mut() {
+        mr.delta *= 10;
+    }
+    assert_eq!(mapping_rules.iter().map(|mr| mr.delta).sum::<u64>(), 20);
+}
+
+#[test]
+fn it_merges_metadata_by_id() {
+    let detailed: MappingRules = serde_json::from_str(BODY).unwrap();
+    let mut lean = detailed.clone();
+    lean.strip_metadata();
+    assert!(lean.metadata_by_id().is_empty());
+
+    lean.merge_metadata_from(&detailed);
+    assert_eq!(lean, detailed);
+}
+
+#[test]
+fn it_iterates_over_collections_with_for_loops() {
+    let mrs: MappingRules = serde_json::from_str(BODY).unwrap();
+
+    let mut borrowed = Vec::new();
+    for mr in &mrs {
+        borrowed.push(mr.clone());
+    }
+    let mut owned = Vec::new();
+    for mr in mrs.clone() {
+        owned.push(mr);
+    }
+
+    assert_eq!(borrowed, Vec::<MappingRule>::from(mrs));
+    assert_eq!(owned, borrowed);
+}
+
+#[test]
+fn it_collects_owned_items() {
+    let all = Vec::<MappingRule>::from(serde_json::from_str::<MappingRules>(BODY).unwrap());
+    let posts: MappingRules = all
+        .into_iter()
+        .filter(|mr| mr.http_method == "POST")
+        .collect();
+
+    assert_eq!(posts.iter().count(), 1);
+    assert!(posts.iter().all(|mr| mr.http_method == "POST"));
+}
+
+#[straitjacket(metadata = "Timestamps", multi_case)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ServicePlan {
+    id: u64,
+}
+
+#[test]
+fn it_parses_collection_keys_in_several_casings() {
+    let snake: ServicePlans =
+        serde_json::from_str(r#"{"service_plans": [{"service_plan": {"id": 1}}]}"#).unwrap();
+    let camel: ServicePlans =
+        serde_json::from_str(r#"{"servicePlans": [{"service_plan": {"id": 1}}]}"#).unwrap();
+
+    assert_eq!(snake, camel);
+    assert_eq!(
+        serde_json::to_value(&camel).unwrap()["service_plans"][0]["service_plan"]["id"],
+        1
+    );
+}
+
+#[test]
+fn it_reports_the_collection_length() {
+    let empty = MappingRules::new_empty();
+    assert_eq!(empty.len(), 0);
+    assert!(empty.is_empty());
+
+    let mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    assert_eq!(mapping_rules.len(), 2);
+    assert!(!mapping_rules.is_empty());
+}
+
+#[test]
+fn it_derefs_to_the_tags() {
+    let mut mapping_rules: MappingRules = serde_json::from_str(BODY).unwrap();
+    assert_eq!(mapping_rules.first(), Some(&mapping_rules[0]));
+    assert_eq!(mapping_rules[0].get_inner().item().http_method, "GET");
+    assert_eq!(mapping_rules[1].get_inner().item().http_method, "POST");
+
+    mapping_rules.truncate(1);
+    assert_eq!(mapping_rules.len(), 1);
+}
+
+mod restricted {
+    use super::*;
+
+    #[straitjacket(metadata = "MyMetadata")]
+    #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+    pub(crate) struct Backup {
+        pub(crate) id: u64,
+    }
+}
+
+mod sibling {
+    use super::restricted::{Backup, BackupTag, Backups};
+
+    pub(crate) fn backup_ids(backups: &Backups) -> Vec<u64> {
+        backups
+            .get_inner()
+            .iter()
+            .filter_map(BackupTag::try_get_inner)
+            .map(|inner| inner.item().id)
+            .collect()
+    }
+
+    pub(crate) fn backups(ids: &[u64]) -> Backups {
+        ids.iter().map(|&id| Backup { id }).collect()
+    }
+}
+
+#[test]
+fn it_keeps_the_item_visibility_on_generated_types() {
+    let backups = sibling::backups(&[1, 2]);
+    assert_eq!(sibling::backup_ids(&backups), vec![1, 2]);
+}
+
+#[straitjacket(metadata = "MyMetadata", id_field = "id", pagination = "PageInfo")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Property<T>
+where
+    T: Clone,
+{
+    id: u64,
+    value: T,
+}
+
+#[test]
+fn it_supports_generic_items() {
+    let body = r##"{
+        "properties": [
+          {
+            "property": {
+              "id": 1,
+              "value": "enabled",
+              "created_at": "2019-04-02T12:57:10Z",
+              "updated_at": "2019-04-02T12:57:10Z",
+              "links": []
+            }
+          },
+          { "property": { "id": 2, "value": "disabled" } }
+        ],
+        "current_page": 1,
+        "total_pages": 1
+        }"##;
+    let properties: Properties<String> = serde_json::from_str(body).unwrap();
+    assert_eq!(properties.len(), 2);
+    assert_eq!(properties.metadata_by_id().keys().collect::<Vec<_>>(), vec![&1]);
+    assert_eq!(
+        properties.page(),
+        Some(&PageInfo { current_page: 1, total_pages: 1 })
+    );
+
+    let values = properties.iter().map(|p| p.value.as_str()).collect::<Vec<_>>();
+    assert_eq!(values, vec!["enabled", "disabled"]);
+
+    let items: Vec<Property<String>> = properties.into();
+    let collected: Properties<String> = items.iter().collect();
+    assert_eq!(Vec::<Property<String>>::from(collected), items);
+
+    let numbers = Properties::from(vec![Property { id: 3, value: 42u8 }]);
+    assert_eq!(numbers.iter().next().map(|p| p.value), Some(42));
+}
+
+#[straitjacket(metadata = "Timestamps")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Quote<'a> {
+    #[serde(borrow)]
+    text: std::borrow::Cow<'a, str>,
+}
+
+#[test]
+fn it_supports_items_with_lifetimes() {
+    let body = r#"{"quotes": [{"quote": {"text": "borrowed"}}]}"#;
+    let quotes: Quotes = serde_json::from_str(body).unwrap();
+    let texts = (&quotes).into_iter().map(|q| q.text.as_ref()).collect::<Vec<_>>();
+    assert_eq!(texts, vec!["borrowed"]);
+
+    let items: Vec<Quote> = quotes.clone().into();
+    let collected: Quotes = items.iter().collect();
+    assert_eq!(collected, quotes);
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CamelMetadata {
+    created_at: String,
+}
+
+#[straitjacket(metadata = "CamelMetadata", forward_serde)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", expecting = "a proxy rule")]
+pub struct ProxyRule {
+    http_method: String,
+}
+
+#[test]
+fn it_forwards_serde_container_attributes() {
+    let proxy_rules: ProxyRules = serde_json::from_str(
+        r#"{"proxy_rules": [{"proxy_rule": {"httpMethod": "GET", "createdAt": "2019-04-02"}}]}"#,
+    )
+    .unwrap();
+    let proxy_rules: Vec<ProxyRuleAndMetadata> = proxy_rules.into();
+    assert_eq!(proxy_rules[0].item().http_method, "GET");
+    assert_eq!(proxy_rules[0].metadata().unwrap().created_at, "2019-04-02");
+
+    let err = serde_json::from_str::<ProxyRules>(r#"{"proxy_rules": [{"proxy_rule": 1}]}"#)
+        .unwrap_err();
+    assert!(err.to_string().contains("a proxy rule"), "{}", err);
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+struct HashableMetadata {
+    created_at: String,
+}
+
+#[straitjacket(
+    metadata = "HashableMetadata",
+    derives = "Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize"
+)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    name: String,
+}
+
+#[test]
+fn it_derives_custom_traits() {
+    let tags = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| Tag { name: name.to_string() })
+            .collect::<Tags>()
+    };
+
+    let mut set = std::collections::HashSet::new();
+    assert!(set.insert(tags(&["a", "b"])));
+    assert!(set.insert(tags(&["b"])));
+    assert!(!set.insert(tags(&["a", "b"])));
+    assert_eq!(set.len(), 2);
+}
+
+#[straitjacket(metadata = "MyMetadata", metadata_field = "meta")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    id: u64,
+    level: u8,
+}
+
+#[test]
+fn it_parses_metadata_under_its_own_key() {
+    let body = r##"{
+        "alerts": [
+          {
+            "alert": {
+              "id": 1,
+              "level": 50,
+              "meta": {
+                "created_at": "2019-04-02T12:57:10Z",
+                "updated_at": "2019-04-02T12:57:10Z",
+                "links": []
+              }
+            }
+          },
+          { "alert": { "id": 2, "level": 90 } }
+        ]
+        }"##;
+    let alerts: Vec<AlertAndMetadata> = serde_json::from_str::<Alerts>(body).unwrap().into();
+    assert_eq!(alerts[0].item(), &Alert { id: 1, level: 50 });
+    assert_eq!(
+        alerts[0].metadata().map(|metadata| metadata.created_at.as_str()),
+        Some("2019-04-02T12:57:10Z")
+    );
+    assert!(alerts[1].metadata().is_none());
+}
+
+#[straitjacket(metadata = "Timestamps", metadata_field = "meta", serialize_null_metadata)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Notice {
+    id: u64,
+}
+
+#[test]
+fn it_serializes_null_metadata_under_its_own_key() {
+    let notices = Notices::from(vec![Notice { id: 1 }]);
+    assert_eq!(
+        serde_json::to_string(&notices).unwrap(),
+        r#"{"notices":[{"notice":{"id":1,"meta":null}}]}"#
+    );
+    assert!(notices.validate_serialization().is_ok());
+}
+
+#[straitjacket(metadata = "MyMetadata", metadata_required)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Provider {
+    id: u64,
+}
+
+#[test]
+fn it_requires_metadata() {
+    let body = r##"{
+        "providers": [
+          {
+            "provider": {
+              "id": 1,
+              "created_at": "2019-04-02T12:57:10Z",
+              "updated_at": "2019-04-02T12:57:10Z",
+              "links": []
+            }
+          }
+        ]
+        }"##;
+    let providers: Vec<ProviderAndMetadata> = serde_json::from_str::<Providers>(body).unwrap().into();
+    assert_eq!(providers[0].metadata.created_at, "2019-04-02T12:57:10Z");
+    assert!(providers[0].metadata().is_some());
+
+    let missing = r#"{"providers": [{"provider": {"id": 2}}]}"#;
+    assert!(serde_json::from_str::<Providers>(missing).is_err());
+}
+
+#[straitjacket(metadata = "MyMetadata", serialize_metadata)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Cluster {
+    id: u64,
+}
+
+#[test]
+fn it_round_trips_metadata() {
+    let body = r##"{
+        "clusters": [
+          {
+            "cluster": {
+              "id": 1,
+              "created_at": "2019-04-02T12:57:10Z",
+              "updated_at": "2019-04-03T12:57:10Z",
+              "links": [{ "rel": "self", "href": "/clusters/1" }]
+            }
+          },
+          { "cluster": { "id": 2 } }
+        ]
+        }"##;
+    let clusters: Clusters = serde_json::from_str(body).unwrap();
+    let value = serde_json::to_value(&clusters).unwrap();
+
+    let first = &value["clusters"][0]["cluster"];
+    assert_eq!(first["created_at"], "2019-04-02T12:57:10Z");
+    assert_eq!(first["links"][0]["href"], "/clusters/1");
+    assert!(value["clusters"][1]["cluster"].get("created_at").is_none());
+    assert_eq!(serde_json::from_value::<Clusters>(value).unwrap(), clusters);
+}
+
+#[straitjacket(metadata_catchall)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Portal {
+    id: u64,
+}
+
+#[test]
+fn it_collects_unknown_metadata_into_a_map() {
+    let body = r##"{
+        "portals": [
+          {
+            "portal": {
+              "id": 1,
+              "created_at": "2019-04-02T12:57:10Z",
+              "links": [{ "rel": "self", "href": "/portals/1" }]
+            }
+          }
+        ]
+        }"##;
+    let portals: Vec<PortalAndMetadata> = serde_json::from_str::<Portals>(body).unwrap().into();
+    assert_eq!(portals[0].item(), &Portal { id: 1 });
+
+    let extra = portals[0].metadata().unwrap();
+    assert_eq!(extra["created_at"], "2019-04-02T12:57:10Z");
+    assert_eq!(extra["links"][0]["rel"], "self");
+    assert!(!extra.contains_key("id"));
+}
+
+mod meta {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+    pub struct PlanMetadata {
+        pub created_at: String,
+    }
+}
+
+#[straitjacket(metadata = "crate::meta::PlanMetadata", serialize_null_metadata)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AccountPlan {
+    id: u64,
+}
+
+#[test]
+fn it_accepts_metadata_type_paths() {
+    let body = r#"{"account_plans": [{"account_plan": {"id": 1, "created_at": "2019-04-02"}}]}"#;
+    let account_plans: Vec<AccountPlanAndMetadata> =
+        serde_json::from_str::<AccountPlans>(body).unwrap().into();
+    assert_eq!(
+        account_plans[0].metadata(),
+        Some(&meta::PlanMetadata { created_at: "2019-04-02".into() })
+    );
+
+    let json = serde_json::to_string(&AccountPlans::from(vec![AccountPlan { id: 2 }])).unwrap();
+    assert!(json.contains(r#""created_at":null"#), "{}", json);
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct LinkMeta<T> {
+    parent_id: T,
+}
+
+#[straitjacket(metadata = "LinkMeta<u64>")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct UsageLimit {
+    id: u64,
+}
+
+#[straitjacket(metadata = "LinkMeta<String>")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    id: u64,
+}
+
+#[test]
+fn it_accepts_generic_metadata_types() {
+    let limits: Vec<UsageLimitAndMetadata> = serde_json::from_str::<UsageLimits>(
+        r#"{"usage_limits": [{"usage_limit": {"id": 1, "parent_id": 7}}]}"#,
+    )
+    .unwrap()
+    .into();
+    assert_eq!(limits[0].metadata(), Some(&LinkMeta { parent_id: 7 }));
+
+    let attachments: Vec<AttachmentAndMetadata> = serde_json::from_str::<Attachments>(
+        r#"{"attachments": [{"attachment": {"id": 1, "parent_id": "abc"}}]}"#,
+    )
+    .unwrap()
+    .into();
+    assert_eq!(
+        attachments[0].metadata(),
+        Some(&LinkMeta { parent_id: "abc".to_string() })
+    );
+}
+
+#[test]
+fn it_parses_single_resource_responses() {
+    let body = r##"{
+        "mapping_rule": {
+          "id": 375837,
+          "metric_id": 2863690,
+          "pattern": "/",
+          "http_method": "GET",
+          "delta": 1,
+          "position": 1,
+          "last": false,
+          "created_at": "2019-04-02T12:57:10Z",
+          "updated_at": "2019-04-02T12:57:10Z",
+          "links": []
+        }
+        }"##;
+    let tag: MappingRuleTag = serde_json::from_str(body).unwrap();
+    assert!(tag.get_inner().metadata().is_some());
+
+    let inner = MappingRuleAndMetadata::from(tag.clone());
+    assert_eq!(inner.item().id, 375837);
+    assert_eq!(MappingRuleTag::from(inner), tag);
+
+    let item = MappingRule::from(tag);
+    let wrapped = serde_json::to_value(MappingRuleTag::from(item.clone())).unwrap();
+    assert_eq!(wrapped["mapping_rule"]["id"], 375837);
+    assert!(wrapped["mapping_rule"].get("created_at").is_none());
+}
+
+#[straitjacket(metadata = "MyMetadata", no_plural_key)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Hook {
+    id: u64,
+}
+
+#[test]
+fn it_parses_collections_without_a_plural_key() {
+    let keyed: MappingRules = serde_json::from_str(BODY).unwrap();
+    assert_eq!(keyed.len(), 2);
+
+    let keyless: Hooks =
+        serde_json::from_str(r#"[{"hook": {"id": 1}}, {"hook": {"id": 2}}]"#).unwrap();
+    assert_eq!(keyless.iter().map(|hook| hook.id).collect::<Vec<_>>(), vec![1, 2]);
+    assert_eq!(
+        serde_json::to_string(&keyless).unwrap(),
+        r#"[{"hook":{"id":1}},{"hook":{"id":2}}]"#
+    );
+    assert!(serde_json::from_str::<Hooks>(r#"{"hooks": []}"#).is_err());
+}
+
+#[straitjacket(metadata = "MyMetadata", paginated)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Message {
+    id: u64,
+}
+
+#[test]
+fn it_parses_paginated_envelopes() {
+    let body = r#"{
+        "messages": [{"message": {"id": 1}}, {"message": {"id": 2}}],
+        "total_count": 12,
+        "per_page": 2
+        }"#;
+    let page: MessagePage = serde_json::from_str(body).unwrap();
+    assert_eq!(page.total_count(), Some(12));
+    assert_eq!(page.per_page(), Some(2));
+    assert_eq!(page.get_inner().len(), 2);
+
+    let messages = Messages::from(page);
+    assert_eq!(messages.iter().map(|message| message.id).collect::<Vec<_>>(), vec![1, 2]);
+
+    let page: MessagePage = serde_json::from_str(r#"{"messages": []}"#).unwrap();
+    assert_eq!(page.total_count(), None);
+}
+
+#[test]
+fn it_takes_pages_out_of_collections() {
+    let messages = Messages::from((1..=5).map(|id| Message { id }).collect::<Vec<_>>());
+
+    let page = messages.take_page(2, 2);
+    assert_eq!(page.current_page(), Some(2));
+    assert_eq!(page.per_page(), Some(2));
+    assert_eq!(page.total_count(), Some(5));
+    assert_eq!(
+        page.into_iter().map(|message| message.id).collect::<Vec<_>>(),
+        vec![3, 4]
+    );
+}
+
+#[test]
+fn it_converts_from_json_strings() {
+    use std::convert::TryFrom;
+
+    let mapping_rules = MappingRules::try_from(BODY).unwrap();
+    assert_eq!(mapping_rules.len(), 2);
+
+    assert!(MappingRules::try_from(r#"{"mapping_rules": ["#).is_err());
+}
+
+#[test]
+fn it_parses_from_strings() {
+    use std::convert::TryFrom;
+
+    let body = BODY.to_string();
+    let mapping_rules = body.parse::<MappingRules>().unwrap();
+    assert_eq!(mapping_rules, MappingRules::try_from(BODY).unwrap());
+
+    assert!("not json".parse::<MappingRules>().is_err());
+}
+
+#[test]
+fn it_displays_as_pretty_json() {
+    let mapping_rules: MappingRules = BODY.parse().unwrap();
+    let displayed = mapping_rules.to_string();
+
+    assert!(displayed.contains("\"mapping_rules\""));
+    assert!(displayed.contains('\n'));
+    assert_eq!(displayed, serde_json::to_string_pretty(&mapping_rules).unwrap());
+}
+
+#[test]
+fn it_unwraps_items() {
+    let mapping_rules: MappingRules = BODY.parse().unwrap();
+
+    let items = mapping_rules.items();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].id, 375841);
+
+    let expected = Vec::<MappingRule>::from(mapping_rules.clone());
+    assert_eq!(mapping_rules.into_items(), expected);
+}
+
+#[straitjacket(metadata = "MyMetadata")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct UsageData {
+    value: u64,
+}
+
+#[straitjacket(metadata = "MyMetadata")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BackendStatus {
+    healthy: bool,
+}
+
+#[straitjacket(metadata = "MyMetadata")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct UpstreamProxy {
+    url: String,
+}
+
+#[straitjacket(metadata = "MyMetadata")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Information {
+    text: String,
+}
+
+#[test]
+fn it_pluralizes_tricky_names() {
+    assert_eq!(UsageDataList::collection_key(), "usage_data");
+    assert_eq!(BackendStatuses::collection_key(), "backend_statuses");
+    assert_eq!(UpstreamProxies::collection_key(), "upstream_proxies");
+    assert_eq!(InformationList::collection_key(), "information");
+    assert_eq!(MappingRules::collection_key(), "mapping_rules");
+}
+
+#[straitjacket(metadata = "MyMetadata")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct OAuthConfig {
+    client_id: String,
+}
+
+#[straitjacket(metadata = "MyMetadata")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct APIKey {
+    value: String,
+}
+
+#[straitjacket(metadata = "MyMetadata")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RedirectURL {
+    href: String,
+}
+
+#[straitjacket(metadata = "MyMetadata")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct OIDCProvider {
+    issuer: String,
+}
+
+#[straitjacket(metadata = "MyMetadata", acronyms = "OpenID")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct OpenIDClient {
+    client_id: String,
+}
+
+#[test]
+fn it_keeps_acronyms_in_snake_case() {
+    assert_eq!(OAuthConfigs::collection_key(), "oauth_configs");
+    assert_eq!(APIKeys::collection_key(), "api_keys");
+    assert_eq!(RedirectURLs::collection_key(), "redirect_urls");
+    assert_eq!(OIDCProviders::collection_key(), "oidc_providers");
+    assert_eq!(OpenIDClients::collection_key(), "openid_clients");
+
+    let body = r#"{"oauth_configs": [{"oauth_config": {"client_id": "abc"}}]}"#;
+    let configs: OAuthConfigs = body.parse().unwrap();
+    assert_eq!(configs.items()[0].client_id, "abc");
+}
+
+#[straitjacket(metadata = "MyMetadata", acronyms = "sso")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Lasso {
+    id: u64,
+}
+
+#[test]
+fn it_only_matches_acronyms_at_word_boundaries() {
+    assert_eq!(Lasso::SNAKE, "lasso");
+    assert_eq!(Lassos::collection_key(), "lassos");
+}
+
+#[straitjacket(metadata = "MyMetadata", tag_variant = "Wrapped")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Sso {
+    token: String,
+}
+
+#[test]
+fn it_names_the_tag_variant() {
+    let body = r#"{"ssos": [{"sso": {"token": "t0k3n"}}]}"#;
+    let ssos: Ssos = body.parse().unwrap();
+
+    match &ssos.get_inner()[0] {
+        SsoTag::Wrapped(inner) => assert_eq!(inner.item().token, "t0k3n"),
+    }
+
+    let tag = SsoTag::from(Sso { token: "other".into() });
+    let SsoTag::Wrapped(inner) = tag;
+    assert_eq!(inner.into_item().token, "other");
+    assert_eq!(Vec::<Sso>::from(ssos).len(), 1);
+}
+
+#[straitjacket(metadata = "MyMetadata", item_field = "rule")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RoutingRule {
+    pattern: String,
+}
+
+#[test]
+fn it_accesses_the_renamed_item_field() {
+    let rule = RoutingRule { pattern: "/".into() };
+    let inner = RoutingRuleAndMetadata::from(RoutingRuleTag::from(rule.clone()));
+    assert_eq!(inner.rule, rule);
+
+    let body = r#"{"routing_rules": [{"routing_rule": {"pattern": "/v1"}}]}"#;
+    let rules: Vec<RoutingRuleAndMetadata> = body.parse::<RoutingRules>().unwrap().into();
+    assert_eq!(rules[0].rule.pattern, "/v1");
+}
+
+#[straitjacket(metadata = "MyMetadata", deny_unknown)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Signup {
+    email: String,
+}
+
+#[test]
+fn it_denies_unknown_fields() {
+    let body = r#"{"signups": [{"signup": {
+        "email": "user@example.com",
+        "created_at": "2019-03-19T09:04:35Z",
+        "updated_at": "2019-03-19T09:04:39Z",
+        "links": []
+    }}]}"#;
+    let signups: Vec<SignupAndMetadata> = body.parse::<Signups>().unwrap().into();
+    assert_eq!(signups[0].item().email, "user@example.com");
+    assert!(signups[0].metadata().is_some());
+
+    let body = r#"{"signups": [{"signup": {"email": "user@example.com", "state": "pending"}}]}"#;
+    let err = body.parse::<Signups>().unwrap_err();
+    assert!(err.to_string().contains("state"));
+}
+
+#[test]
+fn it_views_the_collection_as_a_slice_of_tags() {
+    fn count_tagged(tags: &[MappingRuleTag]) -> usize {
+        tags.len()
+    }
+
+    let mut mapping_rules: MappingRules = BODY.parse().unwrap();
+    assert_eq!(count_tagged(mapping_rules.as_ref()), 2);
+
+    let tags: &mut [MappingRuleTag] = mapping_rules.as_mut();
+    tags.swap(0, 1);
+    assert_eq!(mapping_rules.items()[0].id, 375842);
+}
+
+#[test]
+fn it_exposes_the_derived_keys() {
+    assert_eq!(MappingRule::SNAKE, "mapping_rule");
+    assert_eq!(MappingRule::PLURAL_SNAKE, "mapping_rules");
+    assert_eq!(OAuthConfig::SNAKE, "oauth_config");
+    assert_eq!(UsageData::PLURAL_SNAKE, UsageDataList::collection_key());
+}
+
+trait StraitJacketed {
+    type Plural;
+    type Metadata;
+
+    const SNAKE: &'static str;
+}
+
+#[straitjacket(metadata = "MyMetadata", straitjacketed = "StraitJacketed")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Endpoint {
+    path: String,
+}
+
+#[straitjacket(metadata = "MyMetadata", plural = "Wrappers", straitjacketed = "StraitJacketed")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Wrapper<T> {
+    value: T,
+}
+
+fn parse_collection<T>(json: &str) -> serde_json::Result<T::Plural>
+where
+    T: StraitJacketed,
+    T::Plural: serde::de::DeserializeOwned,
+{
+    serde_json::from_str(json)
+}
+
+#[test]
+fn it_links_items_to_their_collections() {
+    let body = r#"{"endpoints": [{"endpoint": {"path": "/status"}}]}"#;
+    let endpoints: Endpoints = parse_collection::<Endpoint>(body).unwrap();
+    assert_eq!(endpoints.items()[0].path, "/status");
+    assert_eq!(<Endpoint as StraitJacketed>::SNAKE, "endpoint");
+
+    let body = r#"{"wrappers": [{"wrapper": {"value": 3}}]}"#;
+    let wrappers: Wrappers<u8> = parse_collection::<Wrapper<u8>>(body).unwrap();
+    assert_eq!(wrappers.items()[0].value, 3);
+}
+
+#[straitjacket(metadata = "MyMetadata")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum ConfigValue {
+    Text(String),
+    Number(i64),
+    Flag(bool),
+}
+
+#[test]
+fn it_parses_enum_items() {
+    let body = r#"{"config_values": [
+        {"config_value": {"kind": "text", "value": "hello"}},
+        {"config_value": {"kind": "number", "value": 3, "created_at": "2019-03-19T09:04:35Z", "updated_at": "2019-03-19T09:04:39Z", "links": []}}
+    ]}"#;
+    let values: ConfigValues = body.parse().unwrap();
+    assert_eq!(values.items(), vec![&ConfigValue::Text("hello".into()), &ConfigValue::Number(3)]);
+    let values: Vec<ConfigValueAndMetadata> = values.into();
+    assert!(values[0].metadata().is_none());
+    assert!(values[1].metadata().is_some());
+}
+
+#[straitjacket(metadata = "MyMetadata")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Credential {
+    UserKey { user_key: String },
+    AppId { app_id: String, app_key: String },
+}
+
+#[straitjacket(metadata = "MyMetadata")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Quota {
+    Capped { max: u64 },
+    Unlimited { unlimited: bool },
+}
+
+#[test]
+fn it_parses_other_enum_representations() {
+    let body = r#"{"credentials": [
+        {"credential": {"user_key": {"user_key": "abc"}}},
+        {"credential": {"app_id": {"app_id": "id", "app_key": "key"}, "created_at": "2019-03-19T09:04:35Z", "updated_at": "2019-03-19T09:04:39Z", "links": []}}
+    ]}"#;
+    let credentials: Vec<CredentialAndMetadata> = body.parse::<Credentials>().unwrap().into();
+    assert_eq!(credentials[0].item(), &Credential::UserKey { user_key: "abc".into() });
+    assert!(credentials[1].metadata().is_some());
+
+    let body = r#"{"quotas": [{"quota": {"max": 10}}, {"quota": {"unlimited": true}}]}"#;
+    let limits: Quotas = body.parse().unwrap();
+    assert_eq!(limits.items(), vec![&Quota::Capped { max: 10 }, &Quota::Unlimited { unlimited: true }]);
+}
+
+#[straitjacket(
+    metadata = "MyMetadata",
+    no_from = "vec_to_plural, plural_to_vec, plural_to_vec_and_metadata"
+)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Upstream {
+    host: String,
+}
+
+// the user's own conversion, which only keeps reachable hosts
+impl From<Vec<Upstream>> for Upstreams {
+    fn from(upstreams: Vec<Upstream>) -> Self {
+        upstreams.into_iter().filter(|upstream| !upstream.host.is_empty()).collect()
+    }
+}
+
+#[test]
+fn it_leaves_out_suppressed_conversions() {
+    let upstreams = Upstreams::from(vec![
+        Upstream { host: "upstream.example.com".into() },
+        Upstream { host: String::new() },
+    ]);
+    assert_eq!(upstreams.len(), 1);
+    assert_eq!(upstreams.into_items(), vec![Upstream { host: "upstream.example.com".into() }]);
+}
+
+#[straitjacket(metadata = "MyMetadata", no_plural)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ProviderKey {
+    value: String,
+}
+
+// would clash with a generated collection type
+#[derive(Debug)]
+pub struct ProviderKeys;
+
+#[test]
+fn it_generates_only_singular_types() {
+    let body = r#"{"provider_key": {"value": "s3cr3t", "created_at": "2019-03-19T09:04:35Z", "updated_at": "2019-03-19T09:04:39Z", "links": []}}"#;
+    let tag: ProviderKeyTag = serde_json::from_str(body).unwrap();
+    let inner = ProviderKeyAndMetadata::from(tag);
+    assert_eq!(inner.item().value, "s3cr3t");
+    assert!(inner.metadata().is_some());
+
+    let tag = ProviderKeyTag::from(ProviderKey { value: "other".into() });
+    assert_eq!(serde_json::to_string(&tag).unwrap(), r#"{"provider_key":{"value":"other"}}"#);
+    assert_eq!(ProviderKey::SNAKE, "provider_key");
+    let _ = ProviderKeys;
+}
+
+#[test]
+fn it_parses_from_byte_slices() {
+    let mapping_rules = MappingRules::from_slice(BODY.as_bytes()).unwrap();
+    assert_eq!(mapping_rules, BODY.parse::<MappingRules>().unwrap());
+
+    assert!(MappingRules::from_slice(b"{").is_err());
+}
+
+mod transparent {
+    use serde::{Deserialize, Serialize};
+    use straitjacket_macro::straitjacket;
+
+    #[straitjacket(skip_metadata)]
+    #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+    pub struct Token {
+        pub id: u64,
+        pub value: String,
+    }
+}
+
+#[test]
+fn it_views_transparent_collections_as_item_slices() {
+    use std::mem::{align_of, size_of};
+    use transparent::{Token, TokenAndMetadata, TokenTag, Tokens};
+
+    assert_eq!(size_of::<TokenTag>(), size_of::<Token>());
+    assert_eq!(align_of::<TokenTag>(), align_of::<Token>());
+    assert_eq!(size_of::<TokenAndMetadata>(), size_of::<Token>());
+
+    let body = r#"{"tokens":[{"token":{"id":1,"value":"a"}},{"token":{"id":2,"value":"b"}}]}"#;
+    let tokens: Tokens = body.parse().unwrap();
+    let slice = tokens.as_item_slice();
+    assert_eq!(slice.len(), 2);
+    assert_eq!(slice, tokens.items().into_iter().cloned().collect::<Vec<_>>().as_slice());
+    assert!(Tokens::new_empty().as_item_slice().is_empty());
+}
+
+#[test]
+fn it_iterates_paginated_envelopes() {
+    let body = r#"{"messages": [{"message": {"id": 1}}, {"message": {"id": 2}}], "total_count": 12}"#;
+    let page: MessagePage = serde_json::from_str(body).unwrap();
+
+    let mut ids = Vec::new();
+    for message in &page {
+        ids.push(message.id);
+    }
+    assert_eq!(ids, vec![1, 2]);
+
+    let messages = page.into_iter().collect::<Vec<Message>>();
+    assert_eq!(messages, vec![Message { id: 1 }, Message { id: 2 }]);
+}
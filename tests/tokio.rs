@@ -0,0 +1,40 @@
+#![cfg(feature = "tokio")]
+use serde::{Deserialize, Serialize};
+use straitjacket_macro::straitjacket;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct Metadata {
+    created_at: String,
+    updated_at: String,
+}
+
+#[straitjacket]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct MappingRule {
+    id: u64,
+    pattern: String,
+}
+
+#[tokio::test]
+async fn it_reads_collections_from_async_readers() {
+    let body: &[u8] = br#"{
+        "mapping_rules": [
+          { "mapping_rule": { "id": 1, "pattern": "/" } },
+          { "mapping_rule": { "id": 2, "pattern": "/v2" } }
+        ]}"#;
+    let mapping_rules = MappingRules::from_reader(body).await.unwrap();
+    assert_eq!(
+        mapping_rules.into_items(),
+        vec![
+            MappingRule { id: 1, pattern: "/".into() },
+            MappingRule { id: 2, pattern: "/v2".into() },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn it_reports_malformed_async_input_as_invalid_data() {
+    let body: &[u8] = br#"{"mapping_rules": [{"mapping_rule": {"id": "one"}}]}"#;
+    let error = MappingRules::from_reader(body).await.unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
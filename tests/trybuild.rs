@@ -0,0 +1,15 @@
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/keyword_plural_snake.rs");
+    t.compile_fail("tests/ui/unknown_attribute.rs");
+    t.compile_fail("tests/ui/invalid_attribute_value.rs");
+    t.compile_fail("tests/ui/reserved_identifier.rs");
+    t.compile_fail("tests/ui/invalid_attribute_syntax.rs");
+    t.compile_fail("tests/ui/not_a_struct_fn.rs");
+    t.compile_fail("tests/ui/not_a_struct_type_alias.rs");
+    t.compile_fail("tests/ui/generic_unsupported_attribute.rs");
+    t.compile_fail("tests/ui/enum_unsupported_attribute.rs");
+    t.compile_fail("tests/ui/all_field_attribute.rs");
+    t.compile_fail("tests/ui/conflicting_attributes.rs");
+}
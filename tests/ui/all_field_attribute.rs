@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use straitjacket_macro::straitjacket_all;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    created_at: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Account {
+    id: u64,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct User {
+    id: u64,
+    password: String,
+}
+
+straitjacket_all! {
+    Account(id_field = "id"),
+    User(redact = "password"),
+}
+
+fn main() {}
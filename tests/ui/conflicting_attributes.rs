@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use straitjacket_macro::straitjacket;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    created_at: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PageInfo {
+    current_page: u64,
+}
+
+#[straitjacket(skip_metadata, metadata_field = "meta")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Account {
+    id: u64,
+}
+
+#[straitjacket(pagination = "PageInfo", case_insensitive_key)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Service {
+    id: u64,
+}
+
+#[straitjacket(other_variant, tag_variant = "Unknown")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Method {
+    id: u64,
+}
+
+fn main() {}
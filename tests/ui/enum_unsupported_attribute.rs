@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use straitjacket_macro::straitjacket;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    created_at: String,
+}
+
+#[straitjacket(id_field = "id")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum Credential {
+    UserKey { id: u64, user_key: String },
+    AppId { id: u64, app_id: String },
+}
+
+fn main() {}
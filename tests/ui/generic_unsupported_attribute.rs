@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+use straitjacket_macro::straitjacket;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    created_at: String,
+}
+
+#[straitjacket(sort_keys)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Property<T> {
+    id: u64,
+    value: T,
+}
+
+fn main() {}
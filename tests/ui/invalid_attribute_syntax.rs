@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use straitjacket_macro::straitjacket;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Timestamps {
+    created_at: String,
+}
+
+#[straitjacket(
+    metadata = "Vec<",
+    plural = "Match Rules",
+    derives = "Debug,,",
+    wrap_depth = "0",
+    redact = "id, secret key",
+    no_from = "plural_to_slice"
+)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct MatchRule {
+    id: u64,
+}
+
+fn main() {}
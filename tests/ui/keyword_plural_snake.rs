@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use straitjacket_macro::straitjacket;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    created_at: String,
+}
+
+#[straitjacket(plural_snake = "match", name_snake = "type")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Matcher {
+    id: u64,
+}
+
+fn main() {
+    let body = r#"{"match":[{"type":{"id":1,"created_at":"2019-01-01"}}]}"#;
+    let matchers: Matchers = serde_json::from_str(body).unwrap();
+    assert_eq!(matchers.r#match.len(), 1);
+    assert_eq!(serde_json::to_string(&matchers).unwrap(), r#"{"match":[{"type":{"id":1}}]}"#);
+}
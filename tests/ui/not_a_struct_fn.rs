@@ -0,0 +1,6 @@
+use straitjacket_macro::straitjacket;
+
+#[straitjacket]
+pub fn mapping_rule() {}
+
+fn main() {}
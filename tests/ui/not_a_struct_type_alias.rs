@@ -0,0 +1,8 @@
+use straitjacket_macro::straitjacket;
+
+pub struct Rule;
+
+#[straitjacket]
+pub type MappingRule = Rule;
+
+fn main() {}
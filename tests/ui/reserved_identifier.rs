@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use straitjacket_macro::straitjacket;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    created_at: String,
+}
+
+#[straitjacket(name_snake = "self", plural_snake = "crate")]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Matcher {
+    id: u64,
+}
+
+fn main() {}
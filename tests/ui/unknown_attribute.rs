@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use straitjacket_macro::straitjacket;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Timestamps {
+    created_at: String,
+}
+
+#[straitjacket(metadat = "Timestamps", lenient)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Matcher {
+    id: u64,
+}
+
+fn main() {}